@@ -0,0 +1,51 @@
+//! Probes the compiler version so that APIs newer
+//! than the MSRV can be used when they are available,
+//! with a fallback otherwise.
+
+use std::{env, process::Command};
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+
+    let mut pieces = version.split('.');
+    if pieces.next() != Some("rustc 1") {
+        return None;
+    }
+    pieces.next()?.parse().ok()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let minor = match rustc_minor_version() {
+        Some(minor) => minor,
+        None => return,
+    };
+
+    if minor >= 80 {
+        println!("cargo:rustc-check-cfg=cfg(arenalloc_strict_provenance)");
+        println!("cargo:rustc-check-cfg=cfg(arenalloc_hint_black_box)");
+        println!("cargo:rustc-check-cfg=cfg(arenalloc_asan)");
+    }
+
+    // Compiled with `-Zsanitizer=address`.
+    let sanitizers = env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    if sanitizers
+        .split(',')
+        .any(|sanitizer| sanitizer == "address")
+    {
+        println!("cargo:rustc-cfg=arenalloc_asan");
+    }
+
+    // `core::hint::black_box`.
+    if minor >= 66 {
+        println!("cargo:rustc-cfg=arenalloc_hint_black_box");
+    }
+
+    // `<*const T>::addr` and friends.
+    if minor >= 84 {
+        println!("cargo:rustc-cfg=arenalloc_strict_provenance");
+    }
+}
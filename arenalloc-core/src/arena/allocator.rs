@@ -0,0 +1,262 @@
+//! `Allocator` implementations, so collections can
+//! allocate in the arena: `Vec::new_in(&arena)`.
+//! The unstable `core::alloc::Allocator` is implemented with
+//! the `nightly` feature, and `allocator_api2::alloc::Allocator`
+//! with the `allocator-api2` feature, which works on stable.
+//!
+//! Allocations through an `Arena` outlive the regions that are open,
+//! like `ArenaBox`es, while those through a `Scope` end with its region.
+//!
+//! Deallocating gives the memory back only if it was the last
+//! allocation of the current bucket. Growing and shrinking the last
+//! allocation happens in place, anything else is moved.
+
+use core::{
+    alloc::Layout,
+    ptr::{self, NonNull},
+};
+
+use super::{Arena, Scope};
+use crate::compat;
+
+fn slice(ptr: *mut u8, len: usize) -> NonNull<[u8]> {
+    unsafe { NonNull::new_unchecked(ptr::slice_from_raw_parts_mut(ptr, len)) }
+}
+
+impl Arena {
+    fn allocate_layout(&self, layout: Layout) -> Option<NonNull<[u8]>> {
+        let ptr = self.malloc_bytes(layout.size(), layout.align()).ok()?;
+
+        #[cfg(feature = "debug-track")]
+        self.track(layout);
+
+        Some(slice(ptr, layout.size()))
+    }
+
+    unsafe fn grow_layout(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<[u8]>> {
+        let (old, new) = (old_layout.size(), new_layout.size());
+
+        if compat::addr(ptr.as_ptr()) % new_layout.align() == 0
+            && self.resize_in_place(ptr.as_ptr(), old, new)
+        {
+            return Some(slice(ptr.as_ptr(), new));
+        }
+
+        let moved = self.allocate_layout(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), moved.as_ptr() as *mut u8, old);
+        Some(moved)
+    }
+
+    unsafe fn shrink_layout(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<[u8]>> {
+        let (old, new) = (old_layout.size(), new_layout.size());
+
+        if compat::addr(ptr.as_ptr()) % new_layout.align() != 0 {
+            let moved = self.allocate_layout(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), moved.as_ptr() as *mut u8, new);
+            return Some(moved);
+        }
+
+        // Keeps the tail if something was allocated after it.
+        self.resize_in_place(ptr.as_ptr(), old, new);
+        Some(slice(ptr.as_ptr(), new))
+    }
+}
+
+/// Implements an `Allocator` trait for `Arena` and `Scope`.
+/// Allocations through a Scope may live for the whole Scope.
+macro_rules! impl_allocator {
+    ($Allocator:path, $AllocError:path) => {
+        unsafe impl $Allocator for Arena {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, $AllocError> {
+                let ptr = self.allocate_layout(layout).ok_or($AllocError)?;
+                self.keep_in_regions(0, self.checkpoint());
+                Ok(ptr)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.resize_in_place(ptr.as_ptr(), layout.size(), 0);
+            }
+
+            unsafe fn grow(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, $AllocError> {
+                let ptr = self
+                    .grow_layout(ptr, old_layout, new_layout)
+                    .ok_or($AllocError)?;
+                self.keep_in_regions(0, self.checkpoint());
+                Ok(ptr)
+            }
+
+            unsafe fn shrink(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, $AllocError> {
+                let ptr = self
+                    .shrink_layout(ptr, old_layout, new_layout)
+                    .ok_or($AllocError)?;
+                self.keep_in_regions(0, self.checkpoint());
+                Ok(ptr)
+            }
+        }
+
+        unsafe impl $Allocator for Scope<'_> {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, $AllocError> {
+                self.arena().allocate_layout(layout).ok_or($AllocError)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.arena().resize_in_place(ptr.as_ptr(), layout.size(), 0);
+            }
+
+            unsafe fn grow(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, $AllocError> {
+                self.arena()
+                    .grow_layout(ptr, old_layout, new_layout)
+                    .ok_or($AllocError)
+            }
+
+            unsafe fn shrink(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, $AllocError> {
+                self.arena()
+                    .shrink_layout(ptr, old_layout, new_layout)
+                    .ok_or($AllocError)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "nightly")]
+impl_allocator!(core::alloc::Allocator, core::alloc::AllocError);
+
+#[cfg(feature = "allocator-api2")]
+impl_allocator!(
+    allocator_api2::alloc::Allocator,
+    allocator_api2::alloc::AllocError
+);
+
+#[cfg(test)]
+#[cfg(feature = "nightly")]
+mod tests {
+    use crate::arena::Arena;
+
+    use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+
+    #[test]
+    fn test_vec_grows_in_place() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.reserve(4096).unwrap();
+
+            let mut v = Vec::with_capacity_in(4, s);
+            v.push(0u32);
+            let start = v.as_ptr();
+
+            v.extend(1..512);
+            assert_eq!(v.as_ptr(), start);
+            assert_eq!(arena.allocated_bytes(), v.capacity() * 4);
+            assert!(v.iter().copied().eq(0..512));
+
+            v.truncate(10);
+            v.shrink_to_fit();
+            assert_eq!(arena.allocated_bytes(), 40);
+
+            drop(v);
+            assert_eq!(arena.allocated_bytes(), 0);
+        });
+    }
+
+    #[test]
+    fn test_grow_moves() {
+        let arena = Arena::new();
+
+        let mut v = Vec::new_in(&arena);
+        v.push(1u64);
+
+        // Not the last allocation anymore.
+        let b = Box::new_in(2u8, &arena);
+
+        let start = v.as_ptr();
+        v.extend(2..100);
+        assert_ne!(v.as_ptr(), start);
+        assert!(v.iter().copied().eq(1..100));
+        assert_eq!(*b, 2);
+    }
+
+    #[test]
+    fn test_collections() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut queue = VecDeque::new_in(*s);
+            queue.extend(0..100);
+            queue.rotate_left(10);
+
+            let boxed = Box::new_in([1u8; 3], s);
+
+            assert_eq!(queue.pop_front(), Some(10));
+            assert_eq!(*boxed, [1, 1, 1]);
+        });
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "allocator-api2")]
+mod api2_tests {
+    use crate::arena::Arena;
+
+    use allocator_api2::{boxed::Box, vec::Vec};
+
+    #[test]
+    fn test_vec_grows_in_place() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.reserve(4096).unwrap();
+
+            let mut v = Vec::with_capacity_in(4, s);
+            v.push(0u32);
+            let start = v.as_ptr();
+
+            v.extend(1..512);
+            assert_eq!(v.as_ptr(), start);
+            assert!(v.iter().copied().eq(0..512));
+
+            drop(v);
+            assert_eq!(arena.allocated_bytes(), 0);
+        });
+    }
+
+    #[test]
+    fn test_box() {
+        let arena = Arena::new();
+
+        let a = Box::new_in(1u8, &arena);
+        let b = Box::new_in([2u64; 4], &arena);
+
+        assert_eq!((*a, *b), (1, [2; 4]));
+    }
+}
@@ -0,0 +1,2824 @@
+use core::{
+    alloc::Layout,
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr::NonNull,
+};
+
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+
+#[cfg(feature = "debug-poison")]
+use super::poison::{AllocationLog, AllocationRecord};
+
+#[cfg(feature = "bench-support")]
+use super::bench::Counters;
+
+#[cfg(feature = "type-stats")]
+use super::typestats::TypeTable;
+
+#[cfg(feature = "debug-track")]
+use super::track::SiteTable;
+
+use crate::compat;
+
+use super::{
+    bucket::{Bucket, RawBucket, DATA_ALIGN},
+    builder::{Growth, INITIAL_CAPACITY},
+    drops::DropRegistry,
+    handler::HandlerRegistry,
+    store, AllocError, AllocObserver, ArenaBuilder, BackingStore,
+};
+/// An Arena is just a chain of buckets,
+/// linked through their headers:
+/// ```skip
+/// [b1] -> [b2] -> [b3] -> [b4] -> [b5]
+///  |       |       |       |       |
+/// [....]  [...]   [....]  [....]  [....]
+/// ```
+pub struct Arena {
+    /// The first bucket of the chain, which owns all of them.
+    /// `None` until an Arena made by [`Arena::new`] allocates.
+    first: Cell<Option<RawBucket>>,
+
+    /// The bucket that is allocated from, `None` if `first` is.
+    /// Buckets after it are empty.
+    current: Cell<Option<RawBucket>>,
+
+    /// How much larger a new bucket is than the current one.
+    growth: Growth,
+
+    /// The size of the last bucket that followed `growth`. Dedicated
+    /// buckets for large allocations don't change it.
+    growth_base: Cell<usize>,
+
+    /// The maximum capacity of all buckets together.
+    limit: Option<usize>,
+
+    /// Whether to try earlier buckets before growing.
+    first_fit: bool,
+
+    /// Whether new buckets are zeroed.
+    zeroed: bool,
+
+    /// The handlers registered by `Scope::alloc_handler`.
+    handlers: HandlerRegistry,
+
+    /// The values to drop when their memory is given back.
+    drops: DropRegistry,
+
+    /// The regions that are open, innermost last.
+    regions: RefCell<Vec<Region>>,
+
+    /// The number of open regions.
+    depth: Cell<usize>,
+
+    /// The most bytes allocated at once, recorded
+    /// whenever allocations are given back.
+    peak: Cell<usize>,
+
+    /// Notified of allocations, new buckets and resets.
+    observer: Option<Box<dyn AllocObserver>>,
+
+    /// Where the buckets are allocated,
+    /// the global allocator if there is none.
+    store: Option<Rc<dyn BackingStore>>,
+
+    #[cfg(feature = "debug-poison")]
+    log: RefCell<AllocationLog>,
+
+    #[cfg(feature = "bench-support")]
+    pub(super) counters: Cell<Counters>,
+
+    #[cfg(feature = "type-stats")]
+    pub(super) types: TypeTable,
+
+    #[cfg(feature = "debug-track")]
+    pub(super) sites: SiteTable,
+}
+
+#[derive(Copy, Clone)]
+pub struct Scope<'scope> {
+    lifetime: PhantomData<*mut &'scope ()>,
+    arena: &'scope Arena,
+
+    /// The number of regions open when this Scope's region was,
+    /// including its own.
+    depth: usize,
+}
+
+/// Dedicated buckets are rounded up to a multiple of this.
+const PAGE_SIZE: usize = 4096;
+
+/// An open region.
+struct Region {
+    /// Where the region started.
+    start: Marker,
+
+    /// Everything up to here outlives the region,
+    /// because it was allocated for an outer region.
+    keep: Marker,
+}
+
+impl Arena {
+    /// The position of the current bucket in the chain.
+    fn index(&self) -> usize {
+        self.current
+            .get()
+            .map_or(0, |bucket| unsafe { bucket.position() })
+    }
+
+    /// The current bucket, once the chain has one.
+    fn current(&self) -> RawBucket {
+        self.current.get().expect("the Arena has a bucket")
+    }
+
+    /// Returns the buckets, oldest first.
+    fn buckets(&self) -> Buckets<'_> {
+        Buckets {
+            next: self.first.get(),
+            arena: PhantomData,
+        }
+    }
+
+    /// Returns the bucket at `position` in the chain.
+    fn bucket(&self, position: usize) -> RawBucket {
+        self.buckets()
+            .nth(position)
+            .expect("the bucket is in the chain")
+    }
+
+    pub(super) fn handlers(&self) -> &HandlerRegistry {
+        &self.handlers
+    }
+
+    pub(super) fn drops(&self) -> &DropRegistry {
+        &self.drops
+    }
+
+    /// The number of bytes handed out by all buckets,
+    /// from the front and the back, including padding.
+    pub fn allocated_bytes(&self) -> usize {
+        self.buckets().map(|bucket| unsafe { bucket.used() }).sum()
+    }
+
+    /// The largest number of bytes that were allocated at
+    /// once, see [`Arena::allocated_bytes`].
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     s.alloc([0u8; 100]);
+    /// });
+    /// arena.region(|s| {
+    ///     s.alloc([0u8; 10]);
+    /// });
+    ///
+    /// assert_eq!(arena.peak_allocated_bytes(), 100);
+    /// ```
+    pub fn peak_allocated_bytes(&self) -> usize {
+        self.peak.get().max(self.allocated_bytes())
+    }
+
+    /// Remembers the allocated bytes as the peak if they are,
+    /// before some of them are given back.
+    fn record_peak(&self) {
+        self.peak.set(self.peak_allocated_bytes());
+    }
+
+    /// The number of buckets, including the empty ones.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets().count()
+    }
+
+    /// Pushes a new bucket with room for `needed` bytes. That is the next
+    /// growth size, or a dedicated bucket rounded up to whole pages if
+    /// the allocation is larger. A dedicated bucket doesn't change the
+    /// size of the buckets after it.
+    fn grow(&self, needed: usize) -> Result<(), AllocError> {
+        if self.next_bucket(needed) {
+            return Ok(());
+        }
+
+        let len = self.next_size();
+
+        if len >= needed {
+            self.push_bucket(len, needed)?;
+            self.growth_base.set(len);
+            return Ok(());
+        }
+
+        let dedicated = needed
+            .checked_add(PAGE_SIZE - 1)
+            .map_or(needed, |size| size & !(PAGE_SIZE - 1));
+        self.push_bucket(dedicated, needed)
+    }
+
+    /// Pushes a new bucket of at least `min_size` bytes,
+    /// but never smaller than the next growth size.
+    fn grow_to(&self, min_size: usize) -> Result<(), AllocError> {
+        if self.next_bucket(min_size) {
+            return Ok(());
+        }
+
+        let len = self.next_size();
+        self.push_bucket(len.max(min_size), min_size)?;
+
+        if len >= min_size {
+            self.growth_base.set(len);
+        }
+        Ok(())
+    }
+
+    /// The size of the next bucket that follows `growth`. The
+    /// first bucket of an Arena made by [`Arena::new`] doesn't grow.
+    fn next_size(&self) -> usize {
+        let base = self.growth_base.get();
+
+        match self.first.get() {
+            Some(_) => self.growth.next_size(base),
+            None => base,
+        }
+    }
+
+    /// Moves on to the bucket after the current one, if there
+    /// is one with room for `needed` bytes. Buckets after the
+    /// current one are empty at the front, they are only left
+    /// by `rewind`.
+    fn next_bucket(&self, needed: usize) -> bool {
+        match self
+            .current
+            .get()
+            .and_then(|bucket| unsafe { bucket.next() })
+        {
+            Some(bucket) if unsafe { bucket.remaining() } >= needed => {
+                self.current.set(Some(bucket));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Inserts a new bucket of `len` bytes after the current one. If that
+    /// exceeds the limit the bucket is shrunk to the remaining headroom,
+    /// as long as it still has room for `needed` bytes.
+    fn push_bucket(&self, mut len: usize, needed: usize) -> Result<(), AllocError> {
+        if let Some(limit) = self.limit {
+            let headroom = limit.saturating_sub(self.capacity());
+
+            if needed > headroom {
+                return Err(AllocError::LimitExceeded {
+                    limit,
+                    requested: needed,
+                });
+            }
+            len = len.min(headroom);
+        }
+
+        let bucket = Bucket::new(len, self.zeroed, self.store())?.into_raw();
+
+        #[cfg(feature = "bench-support")]
+        self.count(|c| c.buckets += 1);
+
+        if let Some(observer) = &self.observer {
+            observer.on_grow(len);
+        }
+
+        match self.current.get() {
+            Some(current) => unsafe { current.insert_after(bucket) },
+            None => self.first.set(Some(bucket)),
+        }
+        self.current.set(Some(bucket));
+        Ok(())
+    }
+
+    /// Frees every bucket of the chain.
+    ///
+    /// # Safety
+    /// The buckets must not be used afterwards.
+    unsafe fn free_buckets(&mut self) {
+        let mut next = self.first.get();
+
+        while let Some(bucket) = next {
+            next = bucket.next();
+            bucket.dealloc(self.store());
+        }
+    }
+
+    /// Where the buckets are allocated.
+    fn store(&self) -> &dyn BackingStore {
+        store::or_global(self.store.as_ref())
+    }
+
+    /// Whether the buckets are allocated by the global allocator.
+    pub(super) fn uses_global_store(&self) -> bool {
+        self.store.is_none()
+    }
+}
+
+/// An iterator over a chain of buckets,
+/// that borrows the Arena owning them.
+struct Buckets<'a> {
+    next: Option<RawBucket>,
+    arena: PhantomData<&'a Arena>,
+}
+
+impl Buckets<'_> {
+    fn starting_at(bucket: RawBucket) -> Self {
+        Self {
+            next: Some(bucket),
+            arena: PhantomData,
+        }
+    }
+}
+
+impl Iterator for Buckets<'_> {
+    type Item = RawBucket;
+
+    fn next(&mut self) -> Option<RawBucket> {
+        let bucket = self.next?;
+        self.next = unsafe { bucket.next() };
+        Some(bucket)
+    }
+}
+
+/// A position in an Arena, returned by [`Arena::checkpoint`].
+/// Rewinding to it gives back everything allocated after it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    bucket: usize,
+    index: usize,
+    handlers: usize,
+    drops: usize,
+}
+
+impl Marker {
+    /// A marker past every position.
+    const END: Marker = Marker {
+        bucket: usize::MAX,
+        index: usize::MAX,
+        handlers: usize::MAX,
+        drops: usize::MAX,
+    };
+
+    fn position(self) -> (usize, usize, usize, usize) {
+        (self.bucket, self.index, self.handlers, self.drops)
+    }
+
+    fn max(self, other: Self) -> Self {
+        if other.position() > self.position() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if other.position() < self.position() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl Arena {
+    /// Marks the current position, to [`rewind`](Arena::rewind) to later.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// let marker = arena.checkpoint();
+    ///
+    /// arena.alloc(String::from("speculative"));
+    /// arena.rewind(marker);
+    ///
+    /// assert_eq!(arena.allocated_bytes(), 0);
+    /// ```
+    pub fn checkpoint(&self) -> Marker {
+        Marker {
+            bucket: self.index(),
+            index: self
+                .current
+                .get()
+                .map_or(0, |bucket| unsafe { bucket.index() }),
+            handlers: self.handlers.len(),
+            drops: self.drops.len(),
+        }
+    }
+
+    /// Gives back everything allocated after `marker`.
+    /// The buckets are kept, to be reused by later allocations.
+    ///
+    /// This takes `&mut self`, so nothing allocated
+    /// after `marker` can still be in use.
+    ///
+    /// A marker of another Arena is not detected. If this Arena is
+    /// past the same position, it is rewound to it, which is safe
+    /// but probably not what was meant.
+    ///
+    /// # Panics
+    /// Panics if the Arena is not past `marker`, which happens when
+    /// it was rewound to an earlier position after taking `marker`.
+    pub fn rewind(&mut self, marker: Marker) {
+        let is_past = marker.bucket <= self.index()
+            && marker.index <= self.bucket_index(marker.bucket)
+            && marker.handlers <= self.handlers.len()
+            && marker.drops <= self.drops.len();
+        assert!(is_past, "the Arena is not past the marker");
+
+        unsafe { self.rewind_to(marker) }
+    }
+
+    /// Gives back everything allocated after `marker`.
+    /// The buckets are kept, to be reused by later allocations.
+    ///
+    /// # Safety
+    /// `marker` must be from this Arena, which must not have been
+    /// rewound to a position before `marker` since it was taken.
+    /// Nothing allocated after `marker` may be used again.
+    pub unsafe fn rewind_to(&self, marker: Marker) {
+        self.drops.drop_from(marker.drops);
+        self.record_peak();
+
+        // Without buckets, there is nothing to give back but the handlers.
+        if let Some(current) = self.current.get() {
+            let index = self.index();
+            let target = if marker.bucket == index {
+                current
+            } else {
+                self.bucket(marker.bucket)
+            };
+
+            for bucket in Buckets::starting_at(target)
+                .skip(1)
+                .take(index - marker.bucket)
+            {
+                bucket.rewind_to(0);
+            }
+            target.rewind_to(marker.index);
+
+            self.current.set(Some(target));
+        }
+        self.handlers.truncate(marker.handlers);
+
+        for region in self.regions.borrow_mut().iter_mut() {
+            region.keep = region.keep.min(marker);
+        }
+    }
+
+    /// The index of the next free byte of the bucket at `position`,
+    /// 0 for the first bucket if there is none yet.
+    ///
+    /// # Panics
+    /// Panics if there is no bucket at `position`.
+    fn bucket_index(&self, position: usize) -> usize {
+        if position == 0 && self.first.get().is_none() {
+            return 0;
+        }
+        unsafe { self.bucket(position).index() }
+    }
+
+    /// Keeps everything up to `marker` when the regions
+    /// nested deeper than `depth` exit.
+    pub(super) fn keep_in_regions(&self, depth: usize, marker: Marker) {
+        if depth >= self.depth.get() {
+            return;
+        }
+
+        for region in &mut self.regions.borrow_mut()[depth..] {
+            region.keep = region.keep.max(marker);
+        }
+    }
+
+    /// Gives back what the innermost region allocated,
+    /// except what was allocated for outer regions.
+    fn exit_region(&self) {
+        let region = self.regions.borrow_mut().pop().expect("a region is open");
+        self.depth.set(self.depth.get() - 1);
+
+        let target = region.start.max(region.keep).min(self.checkpoint());
+
+        // The Scope of the region can't be used anymore,
+        // and `keep` covers everything allocated for outer regions.
+        unsafe { self.rewind_to(target) }
+    }
+}
+
+/// A position of the allocations from the back of an Arena, returned
+/// by [`Arena::checkpoint_back`]. Rewinding to it gives back what was
+/// allocated from the back after it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BackMarker {
+    bucket: usize,
+
+    /// The bytes allocated from the back of the bucket. Counted
+    /// from its end, so a marker taken before the Arena had
+    /// a bucket is the end of the first one.
+    back: usize,
+}
+
+impl Arena {
+    /// Marks the current position of the allocations from the back,
+    /// to [`rewind_back`](Arena::rewind_back) to later. The front and
+    /// the back of the buckets are rewound independently.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// let frame = arena.checkpoint_back();
+    ///
+    /// for frame in 1..=3 {
+    ///     arena.region(|s| {
+    ///         s.alloc(1u64);
+    ///         s.alloc_back([0u8; 16]);
+    ///     });
+    ///     assert_eq!(arena.allocated_bytes(), frame * 16);
+    /// }
+    ///
+    /// arena.rewind_back(frame);
+    /// assert_eq!(arena.allocated_bytes(), 0);
+    /// ```
+    pub fn checkpoint_back(&self) -> BackMarker {
+        BackMarker {
+            bucket: self.index(),
+            back: self
+                .current
+                .get()
+                .map_or(0, |bucket| unsafe { bucket.capacity() - bucket.end() }),
+        }
+    }
+
+    /// Gives back everything allocated from the back after `marker`,
+    /// in the bucket it was taken in and the buckets after it.
+    ///
+    /// This takes `&mut self`, so nothing allocated
+    /// after `marker` can still be in use.
+    ///
+    /// A marker of another Arena is not detected, like
+    /// with [`Arena::rewind`].
+    ///
+    /// # Panics
+    /// Panics if the back of the Arena is not past `marker`.
+    pub fn rewind_back(&mut self, marker: BackMarker) {
+        let is_past = match self.buckets().nth(marker.bucket) {
+            Some(bucket) => unsafe { marker.back <= bucket.capacity() - bucket.end() },
+            None => self.first.get().is_none() && marker.bucket == 0 && marker.back == 0,
+        };
+        assert!(is_past, "the Arena is not past the marker");
+
+        unsafe { self.rewind_back_to(marker) }
+    }
+
+    /// Gives back everything allocated from the back after `marker`,
+    /// in the bucket it was taken in and the buckets after it.
+    ///
+    /// # Safety
+    /// `marker` must be from this Arena, which must not have been rewound
+    /// from the back to a position before `marker` since it was taken,
+    /// and whose bucket was not freed by [`Arena::trim`] since.
+    /// Nothing allocated from the back after `marker` may be used again.
+    pub unsafe fn rewind_back_to(&self, marker: BackMarker) {
+        self.record_peak();
+
+        if self.first.get().is_none() {
+            return;
+        }
+
+        let bucket = self.bucket(marker.bucket);
+
+        for later in Buckets::starting_at(bucket).skip(1) {
+            later.rewind_back_to(later.capacity());
+        }
+        bucket.rewind_back_to(bucket.capacity() - marker.back);
+    }
+
+    /// Allocates room for `size` values of `T`
+    /// from the back of the current bucket.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub(super) fn malloc_back<T>(&self, size: usize) -> Result<*mut T, AllocError> {
+        let bytes = array_size::<T>(size)?;
+        let align = mem::align_of::<T>();
+
+        let ptr = if bytes == 0 {
+            compat::dangling_mut(align)
+        } else {
+            match self
+                .current
+                .get()
+                .map(|bucket| unsafe { bucket.malloc_back(bytes, align) })
+            {
+                Some(Ok(ptr)) => ptr,
+                _ => {
+                    self.grow(needed(bytes, align))?;
+                    unsafe { self.current().malloc_back(bytes, align)? }
+                }
+            }
+        };
+        self.observe_alloc(ptr, bytes, align);
+
+        #[cfg(feature = "type-stats")]
+        self.types.record(core::any::type_name::<T>(), bytes);
+
+        #[cfg(feature = "debug-track")]
+        self.track(unsafe { Layout::from_size_align_unchecked(bytes, align) });
+
+        Ok(ptr as *mut T)
+    }
+}
+
+/// The size of `len` `T`'s, if it fits in an `isize`
+/// like every allocation has to.
+fn array_size<T>(len: usize) -> Result<usize, AllocError> {
+    mem::size_of::<T>()
+        .checked_mul(len)
+        .filter(|&size| size <= isize::MAX as usize)
+        .ok_or(AllocError::Overflow)
+}
+
+/// The number of bytes an empty bucket needs for an allocation
+/// of `size` bytes aligned to `align`, regardless of the
+/// alignment of its start.
+fn needed(size: usize, align: usize) -> usize {
+    if align <= DATA_ALIGN {
+        size
+    } else {
+        size.saturating_add(align - 1)
+    }
+}
+
+/// Exits the innermost region when dropped,
+/// also when the closure of the region panics.
+struct RegionGuard<'a> {
+    arena: &'a Arena,
+}
+
+impl Drop for RegionGuard<'_> {
+    fn drop(&mut self) {
+        self.arena.exit_region();
+    }
+}
+
+impl Arena {
+    #[inline]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub(super) fn malloc<T>(&self, size: usize) -> Result<*mut T, AllocError> {
+        let bytes = array_size::<T>(size)?;
+        let ptr = self.malloc_bytes(bytes, mem::align_of::<T>())?;
+
+        #[cfg(feature = "debug-poison")]
+        if bytes != 0 {
+            let (bucket, offset) = self.locate(ptr);
+
+            self.log.borrow_mut().push(AllocationRecord {
+                type_name: core::any::type_name::<T>(),
+                size: bytes,
+                bucket,
+                offset,
+            });
+        }
+
+        #[cfg(feature = "type-stats")]
+        self.types.record(
+            if size == 1 {
+                core::any::type_name::<T>()
+            } else {
+                core::any::type_name::<[T]>()
+            },
+            bytes,
+        );
+
+        #[cfg(feature = "debug-track")]
+        self.track(unsafe { Layout::from_size_align_unchecked(bytes, mem::align_of::<T>()) });
+
+        Ok(ptr as *mut T)
+    }
+
+    /// Allocates `size` bytes aligned to `align`,
+    /// which must be a power of two.
+    #[inline]
+    pub(super) fn malloc_bytes(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        self.malloc_bytes_with(size, align, self.first_fit)
+    }
+
+    /// Like `malloc_bytes`, but only allocates in an earlier
+    /// bucket if `first_fit` is true.
+    #[inline]
+    fn malloc_bytes_with(
+        &self,
+        size: usize,
+        align: usize,
+        first_fit: bool,
+    ) -> Result<*mut u8, AllocError> {
+        #[cfg(feature = "bench-support")]
+        self.count(|c| {
+            c.allocations += 1;
+            c.bytes += size;
+        });
+
+        let ptr = if size == 0 {
+            compat::dangling_mut(align)
+        } else {
+            match self
+                .current
+                .get()
+                .map(|bucket| unsafe { bucket.malloc_bytes(size, align) })
+            {
+                Some(Ok(ptr)) => ptr,
+                _ => self.malloc_slow(size, align, first_fit)?,
+            }
+        };
+
+        self.observe_alloc(ptr, size, align);
+        Ok(ptr)
+    }
+
+    /// Tells the observer, if there is one, that
+    /// `size` bytes aligned to `align` were allocated.
+    #[inline]
+    fn observe_alloc(&self, ptr: *mut u8, size: usize, align: usize) {
+        if let Some(observer) = &self.observer {
+            // The allocation succeeded, so its layout is valid.
+            unsafe {
+                observer.on_alloc(
+                    Layout::from_size_align_unchecked(size, align),
+                    NonNull::new_unchecked(ptr),
+                )
+            }
+        }
+    }
+
+    #[cold]
+    fn malloc_slow(
+        &self,
+        size: usize,
+        align: usize,
+        first_fit: bool,
+    ) -> Result<*mut u8, AllocError> {
+        let needed = needed(size, align);
+
+        if first_fit {
+            if let Some(ptr) = self.malloc_first_fit(size, align) {
+                return Ok(ptr);
+            }
+        }
+
+        self.grow(needed)?;
+        Ok(unsafe { self.current().malloc_bytes(size, align)? })
+    }
+
+    /// Allocates in the first bucket before the current one that has room,
+    /// starting at the bucket where the innermost open region started.
+    fn malloc_first_fit(&self, size: usize, align: usize) -> Option<*mut u8> {
+        let first = self.regions.borrow().last().map_or(0, |r| r.start.bucket);
+
+        self.buckets()
+            .skip(first)
+            .take(self.index().saturating_sub(first))
+            .find_map(|bucket| unsafe { bucket.malloc_bytes(size, align).ok() })
+    }
+
+    /// Returns the bucket of `ptr`, and its offset in there.
+    #[cfg(feature = "debug-poison")]
+    fn locate(&self, ptr: *const u8) -> (usize, usize) {
+        if let Some(offset) = self.current().offset_in(ptr) {
+            return (self.index(), offset);
+        }
+
+        self.buckets()
+            .find_map(|bucket| Some((unsafe { bucket.position() }, bucket.offset_in(ptr)?)))
+            .expect("the pointer is in a bucket")
+    }
+}
+
+impl Arena {
+    /// Resizes the allocation of `old` bytes at `ptr` to `new` bytes in place.
+    /// That works if it is the last allocation of the current bucket,
+    /// and the bucket has room. Returns whether it was resized.
+    ///
+    /// # Safety
+    /// `ptr` must be an allocation of `old` bytes by this Arena,
+    /// that is not used beyond `new` bytes afterwards.
+    pub(super) unsafe fn resize_in_place(&self, ptr: *mut u8, old: usize, new: usize) -> bool {
+        if new < old {
+            self.record_peak();
+        }
+        self.current
+            .get()
+            .map_or(false, |bucket| bucket.resize_last(ptr, old, new))
+    }
+}
+
+/// The bytes of the last allocation in a bucket,
+/// which can still be shrunk in place.
+pub(super) struct Claim {
+    /// `None` for a claim of zero bytes before the Arena has a bucket.
+    bucket: Option<RawBucket>,
+    start: usize,
+    pub(super) ptr: *mut u8,
+    pub(super) len: usize,
+}
+
+impl Arena {
+    /// Allocates `len` bytes that can later be given back with `shrink`.
+    pub(super) fn claim(&self, len: usize) -> Result<Claim, AllocError> {
+        // In the current bucket, which is where `shrink` looks.
+        let ptr = self.malloc_bytes_with(len, 1, false)?;
+        let bucket = self.current.get();
+
+        Ok(Claim {
+            bucket,
+            start: bucket.map_or(0, |bucket| unsafe { bucket.index() }) - len,
+            ptr,
+            len,
+        })
+    }
+
+    /// Claims all free bytes of the current bucket, without growing.
+    pub(super) fn claim_rest(&self) -> Claim {
+        let remaining = self
+            .current
+            .get()
+            .map_or(0, |bucket| unsafe { bucket.remaining() });
+        self.claim(remaining).expect("the current bucket has room")
+    }
+
+    /// Shrinks the claim to its first `len` bytes. The rest is given
+    /// back to the bucket, unless something was allocated after it.
+    pub(super) fn shrink(&self, claim: &mut Claim, len: usize) {
+        debug_assert!(len <= claim.len);
+
+        let bucket = match claim.bucket {
+            Some(bucket) => bucket,
+            None => return,
+        };
+
+        unsafe {
+            if bucket.index() == claim.start + claim.len {
+                self.record_peak();
+                bucket.rewind_to(claim.start + len);
+                claim.len = len;
+            }
+        }
+    }
+}
+
+impl Arena {
+    /// Creates an Arena without allocating. The first bucket, of
+    /// 512 bytes, is allocated when the Arena is first used.
+    ///
+    /// This is a `const fn`, so the Arena can be a thread local.
+    /// It is not `Sync`, so it can't be a `static`,
+    /// a [`SyncArena`](super::SyncArena) can.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// thread_local! {
+    ///     static SCRATCH: Arena = const { Arena::new() };
+    /// }
+    ///
+    /// SCRATCH.with(|arena| {
+    ///     assert_eq!(arena.capacity(), 0);
+    ///
+    ///     arena.region(|s| {
+    ///         let line = s.alloc_str("parsed on this thread");
+    ///         assert!(line.starts_with("parsed"));
+    ///     });
+    ///     assert_eq!(arena.capacity(), 512);
+    /// });
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            first: Cell::new(None),
+            current: Cell::new(None),
+            growth: Growth::Doubling,
+            growth_base: Cell::new(INITIAL_CAPACITY),
+            limit: None,
+            first_fit: false,
+            zeroed: true,
+            handlers: HandlerRegistry::new(),
+            drops: DropRegistry::new(),
+            regions: RefCell::new(Vec::new()),
+            depth: Cell::new(0),
+            peak: Cell::new(0),
+            observer: None,
+            store: None,
+            #[cfg(feature = "debug-poison")]
+            log: RefCell::new(AllocationLog::new()),
+            #[cfg(feature = "bench-support")]
+            counters: Cell::new(Counters::ZERO),
+            #[cfg(feature = "type-stats")]
+            types: TypeTable::new(),
+            #[cfg(feature = "debug-track")]
+            sites: SiteTable::new(),
+        }
+    }
+
+    /// Like [`Arena::new`], but allocates the first bucket right
+    /// away, and returns an error if that fails.
+    pub fn try_new() -> Result<Self, AllocError> {
+        ArenaBuilder::new().try_build()
+    }
+
+    /// Creates an Arena whose first bucket has room for `bytes`.
+    pub fn with_capacity(bytes: usize) -> Self {
+        ArenaBuilder::new().initial_capacity(bytes).build()
+    }
+
+    /// Like [`Arena::with_capacity`], but returns an error
+    /// if the first bucket could not be allocated.
+    /// ```
+    /// use arenalloc_core::arena::{AllocError, Arena};
+    ///
+    /// let arena = Arena::try_with_capacity(4096).unwrap();
+    /// assert_eq!(arena.capacity(), 4096);
+    ///
+    /// assert!(matches!(
+    ///     Arena::try_with_capacity(usize::MAX),
+    ///     Err(AllocError::SystemAllocFailed)
+    /// ));
+    /// ```
+    pub fn try_with_capacity(bytes: usize) -> Result<Self, AllocError> {
+        ArenaBuilder::new().initial_capacity(bytes).try_build()
+    }
+
+    pub fn builder() -> ArenaBuilder {
+        ArenaBuilder::new()
+    }
+
+    /// Creates an Arena whose buckets together never
+    /// reserve more than `max_bytes`. Allocations that would
+    /// need more fail with [`AllocError::LimitExceeded`].
+    /// ```
+    /// use arenalloc_core::arena::{AllocError, Arena};
+    ///
+    /// let arena = Arena::with_limit(1024);
+    ///
+    /// arena.region(|s| {
+    ///     assert!(matches!(
+    ///         s.reserve(2048),
+    ///         Err(AllocError::LimitExceeded { limit: 1024, requested: 2048 })
+    ///     ));
+    /// });
+    /// ```
+    pub fn with_limit(max_bytes: usize) -> Self {
+        ArenaBuilder::new().limit(max_bytes).build()
+    }
+
+    /// Creates an Arena in `buf`, which never allocates buckets. Once
+    /// `buf` is full, allocating fails with [`AllocError::LimitExceeded`],
+    /// unless the limit is lifted with [`Arena::set_limit`].
+    /// Opening a region still allocates a little bookkeeping on the heap.
+    ///
+    /// # Panics
+    /// Panics if `buf` is too small for the header of a bucket.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::{mem::MaybeUninit, ptr};
+    ///
+    /// static mut BUF: [MaybeUninit<u8>; 1024] = [MaybeUninit::uninit(); 1024];
+    ///
+    /// // Only done once, so nothing else uses `BUF`.
+    /// let buf = unsafe { &mut *ptr::addr_of_mut!(BUF) };
+    /// let arena = Arena::from_static(buf);
+    ///
+    /// assert!(arena.capacity() <= 1024);
+    /// assert_eq!(arena.limit(), Some(arena.capacity()));
+    /// ```
+    pub fn from_static(buf: &'static mut [MaybeUninit<u8>]) -> Self {
+        let len = buf.len();
+        unsafe { Self::from_raw_parts(NonNull::from(buf).cast(), len) }
+    }
+
+    /// Like [`Arena::from_static`], for `len` bytes at `ptr`
+    /// that don't have to be `'static`, such as a buffer on the stack.
+    ///
+    /// # Safety
+    /// The bytes must be valid for writes, and must not be
+    /// used otherwise until the Arena is dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::{mem::MaybeUninit, ptr::NonNull};
+    ///
+    /// let mut buf = [MaybeUninit::<u8>::uninit(); 256];
+    /// let arena = unsafe { Arena::from_raw_parts(NonNull::from(&mut buf).cast(), buf.len()) };
+    ///
+    /// arena.region(|s| {
+    ///     assert_eq!(s.alloc_slice_copy(&[1, 2, 3]), [1, 2, 3]);
+    /// });
+    /// ```
+    pub unsafe fn from_raw_parts(ptr: NonNull<u8>, len: usize) -> Self {
+        let bucket = Bucket::in_buffer(ptr, len)
+            .expect("the buffer is too small for the header of a bucket")
+            .into_raw();
+        let capacity = bucket.capacity();
+
+        let config = ArenaBuilder::new().limit(capacity).zeroed(false);
+        Self::with_bucket(bucket, capacity, config)
+    }
+
+    pub(super) fn with_config(config: ArenaBuilder) -> Result<Self, AllocError> {
+        let size = config.limit.map_or(config.initial_capacity, |limit| {
+            limit.min(config.initial_capacity)
+        });
+        let bucket = Bucket::new(size, config.zeroed, config.store())?.into_raw();
+
+        Ok(Self::with_bucket(bucket, size, config))
+    }
+
+    /// Creates an Arena whose first bucket is `bucket` of `size` bytes.
+    fn with_bucket(bucket: RawBucket, size: usize, config: ArenaBuilder) -> Self {
+        let mut arena = Self::new();
+
+        arena.first.set(Some(bucket));
+        arena.current.set(Some(bucket));
+        arena.growth = config.growth;
+        arena.growth_base.set(size);
+        arena.limit = config.limit;
+        arena.first_fit = config.first_fit;
+        arena.zeroed = config.zeroed;
+        arena.store = config.store;
+        arena
+    }
+
+    /// Panics if the bookkeeping of the Arena is inconsistent.
+    #[cfg(feature = "bench-support")]
+    pub fn check_invariants(&self) {
+        let index = self.index();
+
+        assert!(
+            self.current.get().map_or(true, |current| self
+                .buckets()
+                .any(|bucket| bucket == current)),
+            "the current bucket is not in the chain"
+        );
+
+        for (i, bucket) in self.buckets().enumerate() {
+            let (position, used, end, capacity) = unsafe {
+                (
+                    bucket.position(),
+                    bucket.index(),
+                    bucket.end(),
+                    bucket.capacity(),
+                )
+            };
+
+            assert_eq!(position, i, "bucket {} has the wrong position", i);
+            assert!(
+                used <= end && end <= capacity,
+                "bucket {} is used beyond its capacity",
+                i
+            );
+            assert!(
+                i <= index || used == 0,
+                "bucket {} is after the current bucket, but not empty",
+                i
+            );
+        }
+    }
+
+    /// Calls `f` with the bytes handed out by each bucket,
+    /// oldest bucket first. Each chunk starts at the data of
+    /// its bucket, so the offsets of allocations are preserved.
+    ///
+    /// Alignment gaps between allocations read as zero, unless the
+    /// buckets are [not zeroed](super::ArenaBuilder::zeroed) or the
+    /// memory was rewound and reused, but padding inside the
+    /// allocated values may be uninitialized, so the chunks
+    /// are `MaybeUninit<u8>`. Allocations from the back are not visited.
+    ///
+    /// This takes `&mut self`, so no region or [`ArenaBox`](super::ArenaBox)
+    /// can be mutating the bytes while they are visited.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc(*b"abc");
+    ///
+    /// let mut used = 0;
+    /// arena.for_each_chunk(|chunk| used += chunk.len());
+    /// assert_eq!(used, 3);
+    /// ```
+    pub fn for_each_chunk(&mut self, f: impl FnMut(&[MaybeUninit<u8>])) {
+        self.used_chunks().for_each(f)
+    }
+
+    /// Returns the bytes handed out by each bucket, oldest bucket
+    /// first, like [`Arena::for_each_chunk`].
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc(*b"GET / HTTP/1.1\r\n");
+    ///
+    /// let chunk = arena.used_chunks().next().unwrap();
+    /// assert_eq!(chunk.len(), 16);
+    /// ```
+    pub fn used_chunks(&mut self) -> impl Iterator<Item = &[MaybeUninit<u8>]> + '_ {
+        self.buckets()
+            .map(|bucket| unsafe { bucket.as_initialized_slice() })
+    }
+
+    /// Writes the bytes handed out by each bucket to `out`, oldest
+    /// bucket first, for instance to send frames that were built
+    /// in the arena. See [`Arena::used_chunks`].
+    ///
+    /// # Safety
+    /// Every byte that was handed out must be initialized, including
+    /// alignment gaps and padding, see [`Arena::for_each_chunk`].
+    /// That holds if only bytes were allocated.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// arena.alloc(*b"hello, ");
+    /// arena.alloc(*b"world");
+    ///
+    /// let mut out = Vec::new();
+    /// unsafe { arena.write_to(&mut out).unwrap() };
+    /// assert_eq!(out, b"hello, world");
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn write_to<W>(&mut self, out: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write + ?Sized,
+    {
+        for chunk in self.used_chunks() {
+            out.write_all(&*(chunk as *const [MaybeUninit<u8>] as *const [u8]))?;
+        }
+        Ok(())
+    }
+
+    /// Installs an observer that is notified of allocations,
+    /// new buckets and resets, or removes it.
+    pub fn set_observer(&mut self, observer: Option<Box<dyn AllocObserver>>) {
+        self.observer = observer;
+    }
+
+    /// Sets the maximum number of bytes the buckets
+    /// may reserve together, or removes the limit.
+    /// Buckets that were already allocated are kept,
+    /// even if they exceed the new limit.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Writes the last [`LOG_CAPACITY`] allocations to `out`,
+    /// oldest first, one per line.
+    ///
+    /// [`LOG_CAPACITY`]: super::poison::LOG_CAPACITY
+    #[cfg(feature = "debug-poison")]
+    pub fn dump_allocations(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        for record in self.log.borrow().records() {
+            writeln!(out, "{}", record)?;
+        }
+        Ok(())
+    }
+
+    /// The total number of bytes reserved by
+    /// the buckets of this Arena.
+    pub fn capacity(&self) -> usize {
+        self.buckets()
+            .map(|bucket| unsafe { bucket.capacity() })
+            .sum()
+    }
+
+    /// Ensures the current bucket has room for at least `additional`
+    /// more bytes, allocating a new bucket of
+    /// `max(next growth size, additional)` bytes if it does not.
+    /// When the capacity already suffices this does nothing.
+    ///
+    /// Note that alignment padding of later allocations
+    /// is taken from the reserved bytes.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    /// arena.reserve(4096).unwrap();
+    /// ```
+    #[doc(alias = "ensure_capacity")]
+    pub fn reserve(&self, additional: usize) -> Result<(), AllocError> {
+        let remaining = self
+            .current
+            .get()
+            .map_or(0, |bucket| unsafe { bucket.remaining() });
+
+        if remaining >= additional {
+            return Ok(());
+        }
+
+        self.grow_to(additional)
+    }
+
+    /// Allocates uninitialized memory for `layout`, for as long as the
+    /// arena is borrowed. Like an [`ArenaBox`](super::ArenaBox), it
+    /// outlives the regions that are open.
+    ///
+    /// Any alignment is honored, also beyond that of the buckets,
+    /// because allocations are aligned by their address. A layout
+    /// of zero bytes returns a dangling pointer, see [`Scope::malloc`].
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::alloc::Layout;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// let layout = Layout::from_size_align(24, 64).unwrap();
+    /// let ptr = arena.alloc_layout(layout).unwrap();
+    /// assert_eq!(ptr.as_ptr() as usize % 64, 0);
+    /// ```
+    #[doc(alias = "alloc_aligned")]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.malloc_bytes(layout.size(), layout.align())?;
+        self.keep_in_regions(0, self.checkpoint());
+
+        #[cfg(feature = "debug-track")]
+        self.track(layout);
+
+        Ok(unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    /// Gives back everything allocated in the Arena, keeping the
+    /// buckets to allocate from again. This takes `&mut self`,
+    /// so nothing allocated before can still be in use.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// for frame in 0..3 {
+    ///     arena.alloc(String::from("scratch space"));
+    ///     arena.reset();
+    ///
+    ///     assert_eq!(arena.allocated_bytes(), 0);
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        unsafe {
+            self.rewind_to(Marker {
+                bucket: 0,
+                index: 0,
+                handlers: 0,
+                drops: 0,
+            });
+            self.rewind_back_to(BackMarker { bucket: 0, back: 0 });
+        }
+
+        if let Some(observer) = &self.observer {
+            observer.on_reset();
+        }
+    }
+
+    /// Like [`Arena::reset`], but also replaces the buckets by a single
+    /// bucket with their combined capacity, so allocating as much as
+    /// before doesn't need to grow. The new bucket is allocated before
+    /// the old ones are freed. If that fails, the old buckets are kept.
+    pub fn reset_coalesced(&mut self) -> Result<(), AllocError> {
+        self.reset();
+
+        if self
+            .first
+            .get()
+            .and_then(|bucket| unsafe { bucket.next() })
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let capacity = self.capacity();
+        let bucket = Bucket::new(capacity, self.zeroed, self.store())?.into_raw();
+
+        #[cfg(feature = "bench-support")]
+        self.count(|c| c.buckets += 1);
+
+        if let Some(observer) = &self.observer {
+            observer.on_grow(capacity);
+        }
+
+        unsafe { self.free_buckets() };
+        self.first.set(Some(bucket));
+        self.current.set(Some(bucket));
+        Ok(())
+    }
+
+    /// Frees empty buckets after the current one, keeping
+    /// up to `keep_bytes` of their capacity for later allocations.
+    /// Buckets are kept in the order of the chain, and the first
+    /// bucket is never freed. Returns the number of bytes freed.
+    ///
+    /// Only buckets after the last one that is in use are freed, also
+    /// counting allocations from the back. A [`BackMarker`] taken in
+    /// a freed bucket can't be rewound to anymore.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::with_capacity(1024);
+    ///
+    /// for _ in 0..100 {
+    ///     arena.alloc([0u8; 1000]);
+    /// }
+    /// arena.reset();
+    ///
+    /// assert!(arena.trim(0) > 0);
+    /// assert_eq!(arena.bucket_count(), 1);
+    /// ```
+    pub fn trim(&mut self, keep_bytes: usize) -> usize {
+        let mut kept = 0;
+
+        self.free_trailing(|bucket| {
+            let capacity = unsafe { bucket.capacity() };
+            let keep = kept + capacity <= keep_bytes;
+
+            if keep {
+                kept += capacity;
+            }
+            keep
+        })
+    }
+
+    /// Frees the empty buckets after the current one, like
+    /// [`Arena::trim`], except for the largest of them.
+    /// Returns the number of bytes freed.
+    pub fn shrink_to_fit(&mut self) -> usize {
+        let largest = self
+            .last_used_bucket()
+            .and_then(|bucket| unsafe { bucket.next() })
+            .map(Buckets::starting_at)
+            .and_then(|buckets| buckets.max_by_key(|&bucket| unsafe { bucket.capacity() }));
+
+        self.free_trailing(|bucket| Some(bucket) == largest)
+    }
+
+    /// The last bucket that is current or in use, after which
+    /// all buckets are empty. `None` if there are no buckets.
+    fn last_used_bucket(&self) -> Option<RawBucket> {
+        let current = self.current.get()?;
+
+        Buckets::starting_at(current)
+            .filter(|&bucket| bucket == current || unsafe { bucket.used() } > 0)
+            .last()
+    }
+
+    /// Frees the empty buckets at the end of the chain for which
+    /// `keep` returns false. Returns the number of bytes freed.
+    fn free_trailing<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(RawBucket) -> bool,
+    {
+        let mut last = match self.last_used_bucket() {
+            Some(bucket) => bucket,
+            None => return 0,
+        };
+        let mut freed = 0;
+
+        // Nothing is borrowed from the Arena, so nothing refers to the
+        // trailing buckets but the chain, which they are unlinked from.
+        unsafe {
+            let trailing = last.split_off();
+
+            for bucket in trailing.into_iter().flat_map(Buckets::starting_at) {
+                if keep(bucket) {
+                    last.insert_after(bucket);
+                    last = bucket;
+                } else {
+                    freed += bucket.capacity();
+                    bucket.dealloc(self.store());
+                }
+            }
+        }
+        freed
+    }
+
+    /// Opens a region, and calls `f` with its [`Scope`].
+    /// Everything allocated through the Scope is given back when
+    /// `f` returns, also when it panics, so nested regions behave
+    /// like a stack. The buckets are kept, to be reused.
+    ///
+    /// Allocations that outlive the region are kept, together with
+    /// everything allocated in the region before them. That happens
+    /// for [`ArenaBox`](super::ArenaBox)es, and for allocations
+    /// through the Scope of an outer region.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let value = s.alloc_ref(10);
+    ///
+    ///     let sum = arena.region(|inner| {
+    ///         let values = inner.alloc_slice_copy(&[1, 2, 3]);
+    ///         values.iter().sum::<i32>()
+    ///     });
+    ///
+    ///     assert_eq!(*value + sum, 16);
+    ///     assert_eq!(arena.allocated_bytes(), 4);
+    /// });
+    ///
+    /// assert_eq!(arena.allocated_bytes(), 0);
+    /// ```
+    pub fn region<F, O>(&self, f: F) -> O
+    where
+        F: for<'scope> FnOnce(&Scope<'scope>) -> O,
+    {
+        let start = self.checkpoint();
+        self.regions
+            .borrow_mut()
+            .push(Region { start, keep: start });
+        self.depth.set(self.depth.get() + 1);
+
+        let _guard = RegionGuard { arena: self };
+
+        let scope = Scope {
+            arena: self,
+            lifetime: PhantomData,
+            depth: self.depth.get(),
+        };
+        f(&scope)
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shows the capacity and the fill level of every bucket.
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("capacity", &self.capacity())
+            .field("limit", &self.limit)
+            .field("buckets", &DebugBuckets(self))
+            .finish()
+    }
+}
+
+struct DebugBuckets<'a>(&'a Arena);
+
+impl fmt::Debug for DebugBuckets<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.buckets().map(|bucket| unsafe {
+                BucketFill {
+                    used: bucket.used(),
+                    capacity: bucket.capacity(),
+                }
+            }))
+            .finish()
+    }
+}
+
+/// Shows as `used/capacity`.
+struct BucketFill {
+    used: usize,
+    capacity: usize,
+}
+
+impl fmt::Debug for BucketFill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.used, self.capacity)
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        unsafe {
+            self.drops.drop_from(0);
+            self.free_buckets()
+        }
+    }
+}
+
+impl<'scope> Scope<'scope> {
+    /// The Arena, for allocating through this Scope.
+    #[inline]
+    pub(super) fn arena(&self) -> &'scope Arena {
+        // Allocating for an outer region while an inner one is open.
+        if self.depth != self.arena.depth.get() {
+            self.arena.keep_in_regions(self.depth, Marker::END);
+        }
+
+        self.arena
+    }
+
+    /// Allocates room for `size` values of `T`, uninitialized.
+    ///
+    /// Allocations of zero bytes, like zero-sized types and empty
+    /// slices, never take room in the arena. They return a dangling
+    /// pointer that is aligned for `T`.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn malloc<T>(&self, size: usize) -> Result<*mut T, AllocError> {
+        self.arena().malloc(size)
+    }
+
+    /// Allocates uninitialized memory for `layout`, valid for
+    /// the whole Scope. Any alignment is honored, see
+    /// [`Arena::alloc_layout`].
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::alloc::Layout;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let ptr = s.alloc_layout(Layout::new::<[u16; 4]>()).unwrap();
+    ///     unsafe { ptr.cast::<[u16; 4]>().as_ptr().write([1, 2, 3, 4]) };
+    /// });
+    /// ```
+    #[doc(alias = "alloc_aligned")]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.arena().malloc_bytes(layout.size(), layout.align())?;
+
+        #[cfg(feature = "debug-track")]
+        self.arena.track(layout);
+
+        Ok(unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    /// Grows the allocation of `old` elements at `ptr` to `new` elements
+    /// in place, which works if nothing was allocated after it and its
+    /// bucket has room. Returns whether it was grown.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let ptr = s.malloc::<u32>(4).unwrap();
+    ///     assert!(unsafe { s.grow_last(ptr, 4, 8) });
+    ///
+    ///     s.malloc::<u32>(1).unwrap();
+    ///     assert!(!unsafe { s.grow_last(ptr, 8, 16) });
+    /// });
+    /// ```
+    ///
+    /// # Safety
+    /// `ptr` must be an allocation of `old` elements through this Scope.
+    pub unsafe fn grow_last<T>(&self, ptr: *mut T, old: usize, new: usize) -> bool {
+        debug_assert!(old <= new);
+
+        match mem::size_of::<T>().checked_mul(new) {
+            Some(bytes) => {
+                let old = mem::size_of::<T>() * old;
+                self.arena().resize_in_place(ptr as *mut u8, old, bytes)
+            }
+            None => false,
+        }
+    }
+
+    /// Shrinks the allocation of `old` elements at `ptr` to `new` elements,
+    /// giving the rest back if nothing was allocated after it.
+    /// Returns whether the bytes were given back.
+    ///
+    /// # Safety
+    /// `ptr` must be an allocation of `old` elements through this Scope,
+    /// of which only the first `new` are used afterwards.
+    pub unsafe fn shrink_last<T>(&self, ptr: *mut T, old: usize, new: usize) -> bool {
+        debug_assert!(new <= old);
+
+        let size = mem::size_of::<T>();
+        self.arena()
+            .resize_in_place(ptr as *mut u8, size * old, size * new)
+    }
+
+    /// Gives back the allocation at `ptr` if nothing was allocated after
+    /// it, so allocating, inspecting and discarding a temporary doesn't
+    /// use up the arena. Returns whether the bytes were given back.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::alloc::Layout;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let layout = Layout::new::<[u64; 8]>();
+    ///     let ptr = s.alloc_layout(layout).unwrap();
+    ///     assert!(unsafe { s.dealloc_last(ptr, layout) });
+    ///     assert_eq!(arena.allocated_bytes(), 0);
+    /// });
+    /// ```
+    ///
+    /// # Safety
+    /// `ptr` must be an allocation of `layout.size()` bytes through
+    /// this Scope, which is not used afterwards.
+    pub unsafe fn dealloc_last(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        self.arena().resize_in_place(ptr.as_ptr(), layout.size(), 0)
+    }
+
+    /// Opens a region nested in this Scope's region, see [`Arena::region`].
+    /// What the child allocates is given back when `f` returns,
+    /// while the allocations of this Scope stay valid.
+    /// ```
+    /// use arenalloc_core::arena::{Arena, Scope};
+    ///
+    /// fn depth_sum(s: &Scope<'_>, depth: u32) -> u32 {
+    ///     if depth == 0 {
+    ///         return 0;
+    ///     }
+    ///
+    ///     let scratch = s.alloc([depth; 64]);
+    ///     let below = s.subscope(|child| depth_sum(child, depth - 1));
+    ///     scratch[0] + below
+    /// }
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let sum = s.subscope(|child| depth_sum(child, 10));
+    ///
+    ///     assert_eq!(sum, 55);
+    ///     assert_eq!(arena.allocated_bytes(), 0);
+    /// });
+    /// ```
+    pub fn subscope<F, O>(&self, f: F) -> O
+    where
+        F: for<'child> FnOnce(&Scope<'child>) -> O,
+    {
+        self.arena.region(f)
+    }
+
+    /// Opens a region nested in this Scope's region like
+    /// [`Scope::subscope`], but only gives back what it allocated if
+    /// `f` returns `Err`. On `Ok` the allocations are kept until the
+    /// region around it exits. For backtracking, where a failed
+    /// attempt shouldn't use up the arena.
+    ///
+    /// Like any region, the child Scope can't escape `f`, so neither
+    /// the output nor the error can borrow from it. What has to outlive
+    /// the attempt is allocated through this Scope, and is always kept,
+    /// like [`ArenaBox`](super::ArenaBox)es.
+    /// ```
+    /// use arenalloc_core::arena::{Arena, Scope};
+    ///
+    /// fn digit_sum(s: &Scope<'_>, input: &str) -> Result<u32, char> {
+    ///     let digits = s.alloc_slice_fill_copy(input.len(), 0);
+    ///
+    ///     for (digit, c) in digits.iter_mut().zip(input.chars()) {
+    ///         *digit = c.to_digit(10).ok_or(c)?;
+    ///     }
+    ///     Ok(digits.iter().sum())
+    /// }
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     assert_eq!(s.try_region(|s| digit_sum(s, "12x4")), Err('x'));
+    ///     assert_eq!(arena.allocated_bytes(), 0);
+    ///
+    ///     assert_eq!(s.try_region(|s| digit_sum(s, "1234")), Ok(10));
+    ///     assert_eq!(arena.allocated_bytes(), 16);
+    /// });
+    /// ```
+    ///
+    /// References to what the attempt allocated can't be
+    /// smuggled out, as that memory is reused after an `Err`:
+    /// ```compile_fail
+    /// use arenalloc_core::arena::Arena;
+    /// use std::cell::Cell;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let escaped = Cell::new(None);
+    ///
+    ///     let _ = s.try_region(|s| {
+    ///         escaped.set(Some(&*s.alloc(1u64)));
+    ///         Err::<(), _>(())
+    ///     });
+    ///
+    ///     let reused = s.alloc(2u64);
+    ///     assert_eq!(escaped.get(), Some(&1));
+    ///     *reused += 1;
+    /// });
+    /// ```
+    #[doc(alias = "transaction")]
+    pub fn try_region<F, O, E>(&self, f: F) -> Result<O, E>
+    where
+        F: for<'child> FnOnce(&Scope<'child>) -> Result<O, E>,
+    {
+        let arena = self.arena;
+
+        arena.region(|child| {
+            let result = f(child);
+
+            if result.is_ok() {
+                // Exiting the region then gives nothing back.
+                arena.keep_in_regions(child.depth - 1, arena.checkpoint());
+            }
+            result
+        })
+    }
+
+    /// Moves `value` into the arena and returns a mutable
+    /// reference to it, valid for the whole Scope.
+    /// The value is never dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let value = s.alloc(10);
+    ///     *value += 1;
+    ///
+    ///     assert_eq!(*value, 11);
+    /// });
+    /// ```
+    #[doc(alias = "alloc_front")]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc<T>(&self, value: T) -> &'scope mut T {
+        self.try_alloc(value).expect("Allocation failed")
+    }
+
+    /// Moves `value` to the back of the current bucket, and returns a
+    /// mutable reference to it. Regions don't give it back, that is
+    /// up to [`Arena::rewind_back`]. The value is never dropped.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_back<T>(&self, value: T) -> &'scope mut T {
+        self.try_alloc_back(value).expect("Allocation failed")
+    }
+
+    /// Like [`Scope::alloc_back`], but returns an error
+    /// if the value could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc_back<T>(&self, value: T) -> Result<&'scope mut T, AllocError> {
+        unsafe {
+            let ptr = self.arena.malloc_back::<T>(1)?;
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Like [`Scope::alloc`], but returns an error
+    /// if the value could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc<T>(&self, value: T) -> Result<&'scope mut T, AllocError> {
+        unsafe {
+            let ptr = self.malloc::<T>(1)?;
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Allocates space for a `T` first, and then writes the result of `f`
+    /// into it. This gives the optimizer the chance to construct the value
+    /// directly in the arena, instead of on the stack, which matters for
+    /// large values. That is not guaranteed, especially without optimizations.
+    /// The value is never dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let buffer = s.alloc_with(|| [0u8; 1024]);
+    ///     buffer[1] = 1;
+    ///
+    ///     assert_eq!(buffer[..2], [0, 1]);
+    /// });
+    /// ```
+    #[inline(always)]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_with<T, F>(&self, f: F) -> &'scope mut T
+    where
+        F: FnOnce() -> T,
+    {
+        let ptr = self.malloc::<T>(1).expect("Allocation failed");
+
+        unsafe {
+            ptr.write(f());
+            &mut *ptr
+        }
+    }
+
+    /// Makes room for `additional` bytes in the current bucket,
+    /// so a workload of a known size doesn't have to grow halfway
+    /// through. See [`Arena::reserve`].
+    #[doc(alias = "ensure_capacity")]
+    pub fn reserve(&self, additional: usize) -> Result<(), AllocError> {
+        self.arena().reserve(additional)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    #[test]
+    fn test_arena() {
+        let arena = Arena::new();
+
+        let alloc = unsafe {
+            let ptr = arena.malloc::<u8>(512).unwrap();
+            ptr.write(20);
+            ptr
+        };
+
+        let alloc2 = unsafe {
+            let ptr = arena.malloc::<u64>(1024 / 8).unwrap();
+            ptr.write(1);
+            ptr
+        };
+
+        let alloc3 = unsafe {
+            let ptr = arena.malloc::<u8>(1).unwrap();
+            ptr.write(1);
+            ptr
+        };
+        assert_eq!(unsafe { alloc.read() }, 20);
+        assert_eq!(unsafe { alloc2.read() }, 1);
+        assert_eq!(unsafe { alloc3.read() }, 1);
+
+        assert_eq!(arena.index(), 2);
+        assert_eq!(arena.bucket_count(), 3);
+    }
+
+    #[test]
+    fn test_alloc() {
+        let arena = Arena::with_limit(1024);
+
+        arena.region(|s| {
+            let a = s.alloc(1u8);
+            let b = s.alloc([2u64; 4]);
+            let c = s.try_alloc(3u16).unwrap();
+
+            *a += 1;
+            b[3] += 1;
+            *c += 1;
+
+            assert_eq!((*a, *b, *c), (2, [2, 2, 2, 3], 4));
+            assert!(s.try_alloc([0u8; 1024]).is_err());
+        });
+    }
+
+    #[test]
+    fn test_alloc_with() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.reserve(8 * 4096).unwrap();
+            let large = s.alloc_with(|| [7u64; 4096]);
+            assert!(large.iter().all(|&n| n == 7));
+            assert_eq!(arena.allocated_bytes(), 8 * 4096);
+
+            let small = s.alloc_with(|| 1u8);
+            *small += 1;
+            assert_eq!(*small, 2);
+        });
+    }
+
+    #[test]
+    fn test_reserve() {
+        let arena = Arena::new();
+
+        arena.reserve(4096).unwrap();
+        let buckets = arena.bucket_count();
+
+        for _ in 0..4096 {
+            arena.malloc::<u8>(1).unwrap();
+        }
+
+        assert_eq!(arena.bucket_count(), buckets);
+        assert!(arena.malloc::<u8>(1).is_ok());
+        assert_eq!(arena.bucket_count(), buckets + 1);
+    }
+
+    #[test]
+    fn test_reserve_noop() {
+        let arena = Arena::new();
+
+        arena.reserve(256).unwrap();
+        arena.reserve(512).unwrap();
+
+        assert_eq!(arena.index(), 0);
+        assert_eq!(arena.bucket_count(), 1);
+    }
+
+    fn bucket_sizes(arena: &Arena) -> Vec<usize> {
+        arena
+            .buckets()
+            .map(|bucket| unsafe { bucket.capacity() })
+            .collect()
+    }
+
+    #[test]
+    fn test_growth_sequence() {
+        for &(num, den, sizes) in &[
+            (2, 1, [512, 1024, 2048, 4096]),
+            (3, 2, [512, 768, 1152, 1728]),
+            (5, 4, [512, 640, 800, 1000]),
+        ] {
+            let arena = Arena::builder().growth_factor(num, den).build();
+
+            for _ in 0..3 {
+                arena.grow(0).unwrap();
+            }
+
+            assert_eq!(bucket_sizes(&arena), sizes);
+        }
+
+        // The first bucket of `Arena::new` is the initial capacity.
+        let arena = Arena::new();
+        for _ in 0..3 {
+            arena.grow(0).unwrap();
+        }
+        assert_eq!(bucket_sizes(&arena), [512, 1024, 2048]);
+    }
+
+    #[test]
+    fn test_lazy_first_bucket() {
+        let mut arena = Arena::new();
+        assert_eq!((arena.capacity(), arena.bucket_count()), (0, 0));
+
+        // Nothing that doesn't need a bucket allocates one.
+        let start = arena.checkpoint();
+        let back = arena.checkpoint_back();
+        arena.region(|s| {
+            s.alloc(());
+            assert_eq!(s.alloc_fmt(format_args!("{}", "")), "");
+        });
+        arena.reset();
+        arena.rewind(start);
+        arena.rewind_back(back);
+        assert_eq!(arena.trim(0) + arena.shrink_to_fit(), 0);
+        arena.reset_coalesced().unwrap();
+        assert_eq!(arena.bucket_count(), 0);
+        assert!(alloc::format!("{:?}", arena).contains("buckets: []"));
+
+        // Markers from before the first bucket are its start and end.
+        arena.malloc::<u8>(8).unwrap();
+        arena.region(|s| {
+            s.alloc_back([0u8; 8]);
+        });
+        assert_eq!(bucket_sizes(&arena), [512]);
+        assert_eq!(arena.allocated_bytes(), 16);
+
+        arena.rewind(start);
+        arena.rewind_back(back);
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+
+    #[test]
+    fn test_values_survive_growth() {
+        let arena = Arena::with_capacity(32);
+
+        arena.region(|s| {
+            let bytes = (0..40u8).map(|n| s.alloc(n)).collect::<Vec<_>>();
+            let words = (0..40u64).map(|n| s.alloc(n << 40)).collect::<Vec<_>>();
+            let slices = (0..10)
+                .map(|n| s.alloc_slice_copy(&[n as u16; 17]))
+                .collect::<Vec<_>>();
+
+            assert!(arena.bucket_count() > 3);
+
+            for (n, byte) in bytes.into_iter().enumerate() {
+                *byte += 1;
+                assert_eq!(*byte as usize, n + 1);
+            }
+            for (n, word) in words.into_iter().enumerate() {
+                assert_eq!(*word, (n as u64) << 40);
+            }
+            for (n, slice) in slices.into_iter().enumerate() {
+                slice[16] = 0;
+                assert_eq!(slice[..16], [n as u16; 16]);
+            }
+        });
+    }
+
+    #[test]
+    fn test_limit() {
+        let arena = Arena::with_limit(4096);
+
+        let err = loop {
+            if let Err(err) = arena.malloc::<u8>(100) {
+                break err;
+            }
+            assert!(arena.capacity() <= 4096);
+        };
+
+        assert_eq!(
+            err,
+            AllocError::LimitExceeded {
+                limit: 4096,
+                requested: 100
+            }
+        );
+        assert_eq!(arena.capacity(), 4096);
+        assert!(arena.malloc::<u8>(1).is_ok());
+    }
+
+    #[test]
+    fn test_limit_is_hard() {
+        for &first_fit in &[false, true] {
+            let arena = Arena::builder().limit(10_000).first_fit(first_fit).build();
+
+            arena.region(|s| {
+                for n in 0..200 {
+                    let _ = match n % 5 {
+                        0 => s.malloc::<u8>(n * 37).map(drop),
+                        1 => s.malloc::<u64>(n).map(drop),
+                        2 => s.try_alloc_back([0u8; 24]).map(drop),
+                        3 => s.reserve(n * 13),
+                        _ => s
+                            .alloc_layout(Layout::from_size_align(n, 256).unwrap())
+                            .map(drop),
+                    };
+                    assert!(arena.capacity() <= 10_000);
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_set_limit() {
+        let mut arena = Arena::with_capacity(512);
+        arena.set_limit(Some(1024));
+
+        assert!(arena.reserve(1024).is_err());
+        assert!(arena.reserve(512).is_ok());
+        arena.malloc::<u8>(512).unwrap();
+
+        assert!(arena.reserve(513).is_err());
+        assert!(arena.reserve(512).is_ok());
+        assert_eq!(arena.capacity(), 1024);
+
+        arena.set_limit(None);
+        assert!(arena.reserve(4096).is_ok());
+    }
+
+    #[test]
+    fn test_from_raw_parts() {
+        let mut buf = [MaybeUninit::<u8>::uninit(); 301];
+        let start = NonNull::from(&mut buf).cast::<u8>();
+
+        // An unaligned start, so the header needs padding.
+        let ptr = unsafe { NonNull::new_unchecked(start.as_ptr().add(1)) };
+        let mut arena = unsafe { Arena::from_raw_parts(ptr, 300) };
+
+        let capacity = arena.capacity();
+        assert!(capacity + mem::size_of::<usize>() * 8 >= 300);
+
+        arena.region(|s| {
+            let bytes = s.malloc::<u8>(capacity).unwrap();
+            assert!(compat::addr(bytes) > compat::addr(start.as_ptr()));
+            assert!(matches!(
+                s.malloc::<u8>(1),
+                Err(AllocError::LimitExceeded { .. })
+            ));
+        });
+        assert_eq!(arena.bucket_count(), 1);
+
+        arena.set_limit(None);
+        arena.region(|s| {
+            s.malloc::<u8>(capacity + 1).unwrap();
+        });
+        assert_eq!(arena.bucket_count(), 2);
+
+        arena.reset_coalesced().unwrap();
+        assert_eq!(arena.bucket_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "too small")]
+    fn test_from_raw_parts_too_small() {
+        let mut buf = [MaybeUninit::<u8>::uninit(); 8];
+        unsafe { Arena::from_raw_parts(NonNull::from(&mut buf).cast(), buf.len()) };
+    }
+
+    #[test]
+    fn test_for_each_chunk() {
+        use crate::compat;
+
+        let mut arena = Arena::new();
+        let mut expected = Vec::new();
+
+        for n in 0..200u32 {
+            let bytes = (n * 0x0101_0101).to_ne_bytes();
+
+            // Single bytes force alignment gaps before the next `u32`.
+            let byte = arena.malloc::<u8>(1).unwrap();
+            let word = arena.malloc::<u32>(1).unwrap();
+            unsafe {
+                byte.write(n as u8);
+                word.write(n * 0x0101_0101);
+            }
+
+            expected.push((compat::addr(byte), vec![n as u8]));
+            expected.push((compat::addr(word), bytes.to_vec()));
+        }
+
+        let mut chunks = Vec::new();
+        arena.for_each_chunk(|chunk| {
+            let bytes = chunk
+                .iter()
+                .map(|b| unsafe { b.assume_init() })
+                .collect::<Vec<_>>();
+            chunks.push((compat::addr(chunk.as_ptr()), bytes));
+        });
+
+        assert!(chunks.len() > 1);
+        let total = chunks.iter().map(|(_, bytes)| bytes.len()).sum::<usize>();
+        assert_eq!(total, arena.allocated_bytes());
+
+        for (addr, bytes) in expected {
+            let (start, chunk) = chunks
+                .iter()
+                .find(|(start, chunk)| (*start..*start + chunk.len()).contains(&addr))
+                .unwrap();
+            let offset = addr - start;
+
+            assert_eq!(chunk[offset..offset + bytes.len()], bytes[..]);
+            if bytes.len() == 1 && offset + 1 < chunk.len() {
+                assert_eq!(chunk[offset + 1..offset + 4], [0; 3]);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_write_to() {
+        let mut arena = Arena::new();
+        let mut expected = Vec::new();
+
+        for n in 0..200u8 {
+            let frame = [n; 100];
+            arena.alloc(frame);
+            expected.extend_from_slice(&frame);
+        }
+        assert!(arena.used_chunks().count() > 1);
+
+        let mut out = Vec::new();
+        unsafe { arena.write_to(&mut out).unwrap() };
+        assert_eq!(out, expected);
+
+        arena.reset();
+        out.clear();
+        unsafe { arena.write_to(&mut out).unwrap() };
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-poison")]
+    fn test_dump_allocations() {
+        use super::super::poison::{ALLOC_POISON, LOG_CAPACITY};
+        use alloc::string::String;
+
+        let arena = Arena::new();
+
+        let ptr = arena.malloc::<u64>(2).unwrap();
+        assert_eq!(unsafe { *(ptr as *const [u8; 16]) }, [ALLOC_POISON; 16]);
+        arena.malloc::<u8>(1).unwrap();
+
+        let mut dump = String::new();
+        arena.dump_allocations(&mut dump).unwrap();
+        assert_eq!(
+            dump,
+            "bucket 0 offset 0: 16 bytes of u64\nbucket 0 offset 16: 1 bytes of u8\n"
+        );
+
+        for _ in 0..LOG_CAPACITY {
+            arena.malloc::<u8>(1).unwrap();
+        }
+        assert_eq!(arena.log.borrow().records().count(), LOG_CAPACITY);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-poison")]
+    #[cfg_attr(
+        all(feature = "sanitizers", arenalloc_asan),
+        ignore = "reads memory given back"
+    )]
+    fn test_poison_reclaimed() {
+        use super::super::poison::FREE_POISON;
+
+        let read = |ptr: *mut u8| unsafe { *(ptr as *const [u8; 16]) };
+        let mut arena = Arena::with_capacity(64);
+
+        let ptr = arena.region(|s| s.malloc::<u8>(16).unwrap());
+        assert_eq!(read(ptr), [FREE_POISON; 16]);
+
+        let marker = arena.checkpoint();
+        let ptr = arena.malloc::<u8>(16).unwrap();
+        let later = arena.malloc::<u8>(1000).unwrap();
+        arena.rewind(marker);
+        assert_eq!(read(ptr), [FREE_POISON; 16]);
+        assert_eq!(read(later), [FREE_POISON; 16]);
+
+        let ptr = arena.malloc::<u8>(16).unwrap();
+        arena.reset();
+        assert_eq!(read(ptr), [FREE_POISON; 16]);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut arena = Arena::new();
+
+        let fill = |arena: &Arena| {
+            for n in 0..1000u32 {
+                arena.alloc(n);
+            }
+        };
+
+        fill(&arena);
+        let (buckets, capacity) = (arena.bucket_count(), arena.capacity());
+        assert!(buckets > 1);
+
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), 0);
+
+        fill(&arena);
+        assert_eq!(arena.bucket_count(), buckets);
+        assert_eq!(arena.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_reset_coalesced() {
+        let mut arena = Arena::new();
+
+        for n in 0..1000u32 {
+            arena.alloc(n);
+        }
+        let capacity = arena.capacity();
+
+        arena.reset_coalesced().unwrap();
+        assert_eq!(arena.bucket_count(), 1);
+        assert_eq!(arena.capacity(), capacity);
+
+        for n in 0..1000u32 {
+            arena.alloc(n);
+        }
+        assert_eq!(arena.bucket_count(), 1);
+    }
+
+    #[test]
+    fn test_trim() {
+        let mut arena = Arena::with_capacity(1024);
+
+        for _ in 0..100 {
+            arena.malloc::<[u8; 1000]>(1).unwrap();
+        }
+        let sizes = bucket_sizes(&arena);
+        assert!(sizes.len() > 3);
+
+        arena.reset();
+        let freed = arena.trim(sizes[1] + sizes[2]);
+        assert_eq!(bucket_sizes(&arena), sizes[..3]);
+        assert_eq!(freed, sizes[3..].iter().sum::<usize>());
+
+        assert_eq!(arena.trim(0), sizes[1] + sizes[2]);
+        assert_eq!(arena.bucket_count(), 1);
+        assert_eq!(arena.trim(0), 0);
+
+        for _ in 0..100 {
+            arena.malloc::<[u8; 1000]>(1).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_trim_keeps_used_buckets() {
+        let mut arena = Arena::with_capacity(1024);
+        let marker = arena.checkpoint();
+
+        arena.region(|s| {
+            for _ in 0..10 {
+                s.alloc([0u8; 1000]);
+            }
+            s.alloc_back(0u64);
+        });
+        let count = arena.bucket_count();
+
+        // The back allocation is in the last bucket.
+        arena.rewind(marker);
+        assert_eq!(arena.trim(0), 0);
+        assert_eq!(arena.bucket_count(), count);
+
+        arena.reset();
+        arena.malloc::<[u8; 1000]>(2).unwrap();
+        arena.trim(0);
+        assert_eq!(arena.bucket_count(), arena.index() + 1);
+        assert_eq!(arena.allocated_bytes(), 2000);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut arena = Arena::with_capacity(1024);
+
+        for _ in 0..100 {
+            arena.malloc::<[u8; 1000]>(1).unwrap();
+        }
+        let sizes = bucket_sizes(&arena);
+        let largest = *sizes[1..].iter().max().unwrap();
+
+        arena.reset();
+        let freed = arena.shrink_to_fit();
+
+        assert_eq!(bucket_sizes(&arena), [sizes[0], largest]);
+        assert_eq!(freed, sizes[1..].iter().sum::<usize>() - largest);
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut arena = Arena::new();
+
+        arena.alloc(1u64);
+        let marker = arena.checkpoint();
+        let used = arena.allocated_bytes();
+
+        for n in 0..1000u64 {
+            arena.alloc(n);
+        }
+        let buckets = arena.bucket_count();
+
+        arena.rewind(marker);
+        assert_eq!(arena.allocated_bytes(), used);
+        assert_eq!(arena.checkpoint(), marker);
+
+        for n in 0..1000u64 {
+            arena.alloc(n);
+        }
+        assert_eq!(arena.bucket_count(), buckets);
+    }
+
+    #[test]
+    #[should_panic(expected = "the Arena is not past the marker")]
+    fn test_rewind_past() {
+        let mut arena = Arena::new();
+
+        let start = arena.checkpoint();
+        arena.alloc(1u64);
+        let marker = arena.checkpoint();
+
+        arena.rewind(start);
+        arena.rewind(marker);
+    }
+
+    #[test]
+    fn test_region_reclaims() {
+        let arena = Arena::new();
+
+        for _ in 0..3 {
+            arena.region(|s| {
+                s.reserve(8192).unwrap();
+                s.malloc::<u8>(8000).unwrap();
+
+                arena.region(|inner| {
+                    inner.malloc::<u8>(100).unwrap();
+                    assert_eq!(arena.allocated_bytes(), 8100);
+                });
+
+                assert_eq!(arena.allocated_bytes(), 8000);
+            });
+
+            assert_eq!(arena.allocated_bytes(), 0);
+        }
+    }
+
+    #[test]
+    fn test_region_keeps_outer_allocations() {
+        let arena = Arena::new();
+
+        arena.region(|outer| {
+            let (value, boxed) = arena.region(|inner| {
+                inner.alloc(1u64);
+                (outer.alloc(2u64), arena.alloc(3u64))
+            });
+
+            arena.region(|inner| {
+                inner.alloc([4u64; 8]);
+            });
+
+            assert_eq!((*value, *boxed), (2, 3));
+            assert_eq!(arena.allocated_bytes(), 24);
+        });
+
+        assert_eq!(arena.allocated_bytes(), 24);
+    }
+
+    #[test]
+    fn test_region_reclaims_on_panic() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let arena = Arena::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            arena.region(|s| {
+                s.alloc(1u64);
+                arena.region(|_| panic!("oops"));
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(arena.allocated_bytes(), 0);
+
+        arena.region(|s| {
+            s.alloc(1u64);
+            assert_eq!(arena.allocated_bytes(), 8);
+        });
+    }
+
+    #[test]
+    fn test_subscope() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let parent = s.alloc(1u64);
+
+            let sum = s.subscope(|child| {
+                let value = child.alloc(2u64);
+                let kept = s.alloc(3u64);
+
+                *value + *kept + child.subscope(|grandchild| *grandchild.alloc(4u64))
+            });
+
+            assert_eq!(*parent + sum, 10);
+            assert_eq!(arena.allocated_bytes(), 24);
+        });
+    }
+
+    #[test]
+    fn test_alloc_back() {
+        let mut arena = Arena::new();
+        let start = arena.checkpoint_back();
+
+        arena.region(|s| {
+            let front = s.alloc([1u8; 256]);
+            let back = s.alloc_back([2u8; 200]);
+
+            // Doesn't fit between the front and the back anymore.
+            let grown = s.alloc([3u8; 100]);
+            assert_eq!(arena.index(), 1);
+
+            assert_eq!((front[0], back[0], grown[0]), (1, 2, 3));
+        });
+        assert_eq!(arena.allocated_bytes(), 200);
+
+        let marker = arena.checkpoint_back();
+        arena.region(|s| {
+            s.alloc_back(0u64);
+        });
+        assert_eq!(arena.allocated_bytes(), 208);
+
+        arena.rewind_back(marker);
+        assert_eq!(arena.allocated_bytes(), 200);
+
+        arena.rewind_back(start);
+        assert_eq!(arena.allocated_bytes(), 0);
+
+        arena.region(|s| {
+            s.alloc_back(0u64);
+        });
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the Arena is not past the marker")]
+    fn test_rewind_back_past() {
+        let mut arena = Arena::new();
+
+        arena.region(|s| {
+            s.alloc_back(0u64);
+        });
+        let marker = arena.checkpoint_back();
+        arena.reset();
+
+        arena.rewind_back(marker);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        #[repr(align(64))]
+        struct Aligned;
+
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.malloc::<u8>(511).unwrap();
+
+            let unit = s.alloc(());
+            let aligned = s.alloc(Aligned);
+            let empty = s.alloc_slice_copy::<u64>(&[]);
+            let back = s.alloc_back(Aligned);
+            let layout = s.alloc_layout(Layout::new::<[u32; 0]>()).unwrap();
+
+            assert_eq!(crate::compat::addr(aligned) % 64, 0);
+            assert_eq!(crate::compat::addr(back) % 64, 0);
+            assert_eq!(crate::compat::addr(empty.as_ptr()) % 8, 0);
+            assert_eq!(crate::compat::addr(layout.as_ptr()) % 4, 0);
+            assert_eq!(*unit, ());
+
+            assert_eq!(arena.allocated_bytes(), 511);
+            assert_eq!(arena.index(), 0);
+        });
+    }
+
+    #[test]
+    fn test_stats() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.alloc([0u8; 500]);
+            s.alloc([0u8; 100]);
+            s.alloc_back(0u32);
+        });
+        arena.alloc(1u16);
+
+        assert_eq!(arena.bucket_count(), 2);
+        // Regions don't give back allocations from the back.
+        assert_eq!(arena.allocated_bytes(), 6);
+        assert_eq!(arena.peak_allocated_bytes(), 604);
+        assert_eq!(
+            std::format!("{:?}", arena),
+            "Arena { allocated_bytes: 6, capacity: 1536, limit: None, \
+             buckets: [2/512, 4/1024] }"
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let arena = Arena::new();
+
+        assert_eq!(arena.malloc::<u64>(usize::MAX), Err(AllocError::Overflow));
+        assert_eq!(
+            arena.malloc::<u8>(isize::MAX as usize + 1),
+            Err(AllocError::Overflow)
+        );
+        assert_eq!(
+            arena.malloc_back::<u32>(usize::MAX / 2),
+            Err(AllocError::Overflow)
+        );
+        assert_eq!(
+            AllocError::Overflow.to_string(),
+            "the size of the allocation overflows"
+        );
+    }
+
+    #[test]
+    fn test_alloc_layout() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            for &align in &[1, 8, 64, 4096] {
+                let layout = Layout::from_size_align(3, align).unwrap();
+                let ptr = s.alloc_layout(layout).unwrap();
+                assert_eq!(crate::compat::addr(ptr.as_ptr()) % align, 0);
+            }
+
+            let kept = arena.alloc_layout(Layout::new::<u64>()).unwrap();
+            unsafe { kept.as_ptr().write(1) };
+        });
+
+        assert!(arena.allocated_bytes() >= 8);
+    }
+
+    #[test]
+    fn test_over_aligned() {
+        #[repr(align(64))]
+        struct CacheLine([u8; 64]);
+
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            for n in 0..100 {
+                let line = s.alloc(CacheLine([n; 64]));
+                assert_eq!(crate::compat::addr(line) % 64, 0);
+                assert_eq!(line.0[63], n);
+            }
+
+            // Larger than a bucket, aligned to a page.
+            let layout = Layout::from_size_align(8192, 4096).unwrap();
+            let page = s.alloc_layout(layout).unwrap();
+            assert_eq!(crate::compat::addr(page.as_ptr()) % 4096, 0);
+            unsafe { page.as_ptr().write_bytes(1, 8192) };
+        });
+    }
+
+    #[test]
+    fn test_grow_shrink_last() {
+        let arena = Arena::new();
+
+        arena.region(|s| unsafe {
+            let ptr = s.malloc::<u64>(2).unwrap();
+            ptr.write(1);
+
+            assert!(s.grow_last(ptr, 2, 8));
+            assert_eq!(arena.allocated_bytes(), 64);
+            assert!(!s.grow_last(ptr, 8, 1024));
+            assert!(!s.grow_last(ptr, 8, usize::MAX));
+
+            assert!(s.shrink_last(ptr, 8, 4));
+            assert_eq!(arena.allocated_bytes(), 32);
+            assert_eq!(ptr.read(), 1);
+
+            s.malloc::<u8>(1).unwrap();
+            assert!(!s.grow_last(ptr, 4, 5));
+            assert!(!s.shrink_last(ptr, 4, 1));
+            assert_eq!(arena.allocated_bytes(), 33);
+        });
+    }
+
+    #[test]
+    fn test_dealloc_last() {
+        let arena = Arena::new();
+
+        arena.region(|s| unsafe {
+            let layout = Layout::new::<[u32; 4]>();
+            let first = s.alloc_layout(layout).unwrap();
+            let second = s.alloc_layout(layout).unwrap();
+
+            assert!(!s.dealloc_last(first, layout));
+            assert!(s.dealloc_last(second, layout));
+            assert!(s.dealloc_last(first, layout));
+            assert_eq!(arena.allocated_bytes(), 0);
+
+            // Reuses the memory.
+            assert_eq!(s.alloc_layout(layout).unwrap(), first);
+        });
+    }
+
+    #[test]
+    fn test_try_region() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.alloc(1u32);
+
+            let result = s.try_region(|s| {
+                let kept = s.try_region(|s| Ok::<_, u32>(*s.alloc(2u32)))?;
+                s.alloc_slice_copy(&[3u32; 8]);
+                assert_eq!(arena.allocated_bytes(), 40);
+                Err::<(), _>(kept)
+            });
+            assert_eq!(result, Err(2));
+            assert_eq!(arena.allocated_bytes(), 4);
+
+            // An attempt that fails after growing the arena.
+            let result = s.try_region(|s| {
+                s.alloc_slice_fill_copy(4096, 0u8);
+                Err::<(), _>("too long")
+            });
+            assert_eq!(result, Err("too long"));
+            assert_eq!(arena.allocated_bytes(), 4);
+        });
+    }
+
+    #[test]
+    fn test_try_region_keeps() {
+        let arena = Arena::new();
+
+        let boxed = arena.region(|outer| {
+            arena.region(|s| {
+                // Allocated for the outer region.
+                let result = s.try_region(|_| {
+                    outer.alloc(1u32);
+                    Err::<(), _>(())
+                });
+                assert!(result.is_err());
+                assert_eq!(arena.allocated_bytes(), 4);
+
+                // Outlives the region.
+                let mut boxed = None;
+                let result = s.try_region(|_| {
+                    boxed = Some(arena.alloc(2u32));
+                    Err::<(), _>(())
+                });
+                assert!(result.is_err());
+                assert_eq!(arena.allocated_bytes(), 8);
+
+                // While a nested region is open.
+                arena.region(|inner| {
+                    inner.alloc(3u32);
+
+                    let result = s.try_region(|s| {
+                        s.alloc(4u32);
+                        Err::<(), _>(())
+                    });
+                    assert!(result.is_err());
+                    assert_eq!(arena.allocated_bytes(), 12);
+
+                    let result = s.try_region(|s| Ok::<_, ()>(*s.alloc(5u32)));
+                    assert_eq!(result, Ok(5));
+                    assert_eq!(arena.allocated_bytes(), 16);
+                });
+                assert_eq!(arena.allocated_bytes(), 8);
+
+                boxed.unwrap()
+            })
+        });
+
+        assert_eq!(*boxed, 2);
+        assert_eq!(arena.allocated_bytes(), 8);
+    }
+
+    #[test]
+    fn test_try_region_panics() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let arena = Arena::new();
+
+        let boxed = arena.region(|s| {
+            let boxed = arena.alloc(1u32);
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                s.try_region(|s| -> Result<(), ()> {
+                    s.alloc(2u32);
+                    panic!("boom")
+                })
+            }));
+            assert!(result.is_err());
+
+            // The box is still kept when the region exits.
+            boxed
+        });
+
+        assert_eq!(*boxed, 1);
+        assert_eq!(arena.allocated_bytes(), 4);
+    }
+
+    #[test]
+    fn test_builder() {
+        let arena = Arena::builder()
+            .initial_capacity(100)
+            .growth(Growth::Fixed(200))
+            .limit(450)
+            .build();
+
+        for _ in 0..2 {
+            arena.grow(0).unwrap();
+        }
+        assert_eq!(bucket_sizes(&arena), [100, 200, 150]);
+
+        let arena = Arena::builder()
+            .growth(Growth::Custom(|size| size + 1))
+            .build();
+        arena.grow(0).unwrap();
+        assert_eq!(bucket_sizes(&arena), [512, 513]);
+    }
+
+    #[test]
+    fn test_large_allocation() {
+        let arena = Arena::with_capacity(512);
+
+        arena.region(|s| {
+            let ptr = s.malloc::<u8>(10_000).unwrap();
+            unsafe { ptr.write_bytes(1, 10_000) };
+
+            // The rest of the dedicated bucket is used first.
+            s.malloc::<u8>(100).unwrap();
+            assert_eq!(bucket_sizes(&arena), [512, 12288]);
+
+            s.malloc::<u8>(2000).unwrap();
+            assert_eq!(bucket_sizes(&arena), [512, 12288]);
+
+            s.malloc::<u8>(500).unwrap();
+            assert_eq!(bucket_sizes(&arena), [512, 12288, 1024]);
+        });
+    }
+
+    #[test]
+    fn test_first_fit() {
+        for &(first_fit, buckets) in &[(false, 3), (true, 2)] {
+            let arena = Arena::builder().first_fit(first_fit).build();
+
+            arena.region(|s| {
+                s.malloc::<u8>(400).unwrap();
+                s.malloc::<u8>(1000).unwrap();
+                s.malloc::<u8>(100).unwrap();
+
+                assert_eq!(arena.bucket_count(), buckets);
+            });
+            assert_eq!(arena.allocated_bytes(), 0);
+        }
+    }
+
+    #[test]
+    fn test_first_fit_in_region() {
+        let arena = Arena::builder().first_fit(true).build();
+
+        arena.region(|s| {
+            s.malloc::<u8>(400).unwrap();
+            s.malloc::<u8>(1000).unwrap();
+
+            arena.region(|inner| {
+                // Bucket 0 has room, but the region started in bucket 1.
+                inner.malloc::<u8>(100).unwrap();
+                assert_eq!(arena.bucket_count(), 3);
+            });
+
+            assert_eq!(arena.allocated_bytes(), 1400);
+        });
+    }
+}
@@ -0,0 +1,144 @@
+use core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+};
+
+use super::{AllocError, Arena};
+
+/// An owning pointer to a value in the arena, tied to
+/// the borrow of the [`Arena`] instead of to a [`Scope`](super::Scope).
+/// It can be returned from [`Arena::region`], so the result of a region
+/// can outlive it. The value is dropped when the box is dropped.
+/// ```
+/// use arenalloc_core::arena::Arena;
+///
+/// let arena = Arena::new();
+///
+/// let result = arena.region(|s| {
+///     let sum = (0..10).map(|n| *s.alloc_ref(n)).sum::<u32>();
+///     arena.alloc(sum)
+/// });
+///
+/// assert_eq!(*result, 45);
+/// ```
+pub struct ArenaBox<'arena, T> {
+    ptr: NonNull<T>,
+    lifetime: PhantomData<&'arena Arena>,
+    marker: PhantomData<T>,
+}
+
+impl<'arena, T> ArenaBox<'arena, T> {
+    /// # Safety
+    /// `ptr` must point to an initialized `T` that
+    /// is valid for `'arena`, which nothing else owns.
+    pub(super) unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            lifetime: PhantomData,
+            marker: PhantomData,
+        }
+    }
+
+    /// Consumes the box, and returns a pointer to the value,
+    /// which is never dropped. See [`FrozenArena::get`](super::FrozenArena::get).
+    pub fn into_raw(this: Self) -> *mut T {
+        let ptr = this.ptr.as_ptr();
+        core::mem::forget(this);
+        ptr
+    }
+
+    /// Moves the value out of the arena.
+    pub fn into_inner(this: Self) -> T {
+        let value = unsafe { this.ptr.as_ptr().read() };
+        core::mem::forget(this);
+        value
+    }
+}
+
+impl<T> Drop for ArenaBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.ptr.as_ptr()) }
+    }
+}
+
+impl<T> Deref for ArenaBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for ArenaBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl Arena {
+    /// Moves `value` into the arena, for as long as the arena is borrowed.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc<T>(&self, value: T) -> ArenaBox<'_, T> {
+        self.try_alloc(value).expect("Allocation failed")
+    }
+
+    /// Like [`Arena::alloc`], but returns an error
+    /// if the value could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc<T>(&self, value: T) -> Result<ArenaBox<'_, T>, AllocError> {
+        unsafe {
+            let ptr = self.malloc::<T>(1)?;
+            self.keep_in_regions(0, self.checkpoint());
+
+            ptr.write(value);
+            Ok(ArenaBox::from_raw(NonNull::new_unchecked(ptr)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaBox;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::{string::String, vec::Vec};
+    use core::cell::Cell;
+
+    #[test]
+    fn test_escape_region() {
+        let arena = Arena::new();
+
+        let boxes = arena.region(|s| {
+            let words = ["a", "b", "c"].map(|w| s.alloc_str(w));
+            words
+                .iter()
+                .map(|w| arena.alloc(String::from(*w)))
+                .collect::<Vec<_>>()
+        });
+
+        let mut joined = String::new();
+        for b in &boxes {
+            joined.push_str(b);
+        }
+        assert_eq!(joined, "abc");
+    }
+
+    #[test]
+    fn test_drop() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        let a = arena.region(|_| arena.alloc(DropCount(&drops)));
+        let b = arena.alloc(DropCount(&drops));
+        assert_eq!(drops.get(), 0);
+
+        drop(a);
+        assert_eq!(drops.get(), 1);
+
+        let b = ArenaBox::into_inner(b);
+        assert_eq!(drops.get(), 1);
+        drop(b);
+        assert_eq!(drops.get(), 2);
+    }
+}
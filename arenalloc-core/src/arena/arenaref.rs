@@ -0,0 +1,80 @@
+use core::{marker::PhantomData, ops::Deref, ptr::NonNull};
+
+use super::Scope;
+
+/// A shared reference to a value in the arena,
+/// branded with the lifetime of the [`Scope`] it was
+/// allocated in. Unlike `&'scope T` the lifetime is
+/// invariant, so an `ArenaRef` of one region can
+/// never be passed where a reference of another region
+/// is expected.
+///
+/// It has the same size as a reference, and
+/// `Option<ArenaRef<T>>` has the same size as a pointer.
+pub struct ArenaRef<'scope, T> {
+    ptr: NonNull<T>,
+    lifetime: PhantomData<(&'scope T, *mut &'scope ())>,
+}
+
+impl<T> Clone for ArenaRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaRef<'_, T> {}
+
+impl<'scope, T> ArenaRef<'scope, T> {
+    /// # Safety
+    /// `ptr` must point to an initialized `T` that
+    /// is valid for `'scope`.
+    pub(crate) unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            lifetime: PhantomData,
+        }
+    }
+
+    // These are associated functions, like the ones of `Rc`,
+    // so they don't shadow methods of `T`.
+
+    pub fn as_ptr(this: Self) -> *const T {
+        this.ptr.as_ptr()
+    }
+
+    pub fn get(this: Self) -> &'scope T {
+        unsafe { &*this.ptr.as_ptr() }
+    }
+
+    /// Returns true if both point to the same allocation.
+    pub fn ptr_eq(this: Self, other: Self) -> bool {
+        this.ptr == other.ptr
+    }
+}
+
+impl<T> Deref for ArenaRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        ArenaRef::get(*self)
+    }
+}
+
+impl<'scope> Scope<'scope> {
+    /// Moves `value` into the arena and returns a
+    /// branded reference to it. The value is never dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let r = s.alloc_ref(10);
+    ///     assert_eq!(*r, 10);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_ref<T>(&self, value: T) -> ArenaRef<'scope, T> {
+        unsafe { ArenaRef::from_raw(NonNull::from(self.alloc(value))) }
+    }
+}
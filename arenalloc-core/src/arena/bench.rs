@@ -0,0 +1,69 @@
+//! Hooks for driving an Arena from external benchmarks,
+//! enabled by the `bench-support` feature.
+
+use super::Arena;
+use crate::compat;
+
+/// What an Arena did since it was created,
+/// or since [`Arena::reset_counters`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// The number of allocations.
+    pub allocations: usize,
+
+    /// The bytes requested, without alignment padding.
+    pub bytes: usize,
+
+    /// The number of buckets allocated.
+    pub buckets: usize,
+}
+
+impl Counters {
+    pub(super) const ZERO: Counters = Counters {
+        allocations: 0,
+        bytes: 0,
+        buckets: 0,
+    };
+}
+
+impl Arena {
+    pub(super) fn count(&self, f: impl FnOnce(&mut Counters)) {
+        let mut counters = self.counters.get();
+        f(&mut counters);
+        self.counters.set(counters);
+    }
+
+    /// Allocates `size` bytes aligned to `align`, hiding the
+    /// arguments and the result from the optimizer, so the
+    /// allocation is not optimized away.
+    ///
+    /// # Panics
+    /// Panics if `align` is not a power of two, or if allocating fails.
+    /// ```
+    /// use arenalloc_core::arena::{workloads, Arena};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// for (size, align) in workloads::many_small(100) {
+    ///     arena.alloc_bytes_black_box(size, align);
+    /// }
+    ///
+    /// assert_eq!(arena.counters().allocations, 100);
+    /// arena.check_invariants();
+    /// ```
+    pub fn alloc_bytes_black_box(&self, size: usize, align: usize) -> *mut u8 {
+        let (size, align) = compat::black_box((size, align));
+        assert!(align.is_power_of_two(), "{} is not a power of two", align);
+
+        let ptr = self.malloc_bytes(size, align).expect("Allocation failed");
+        compat::black_box(ptr)
+    }
+
+    pub fn counters(&self) -> Counters {
+        self.counters.get()
+    }
+
+    pub fn reset_counters(&self) {
+        self.counters.set(Counters::default());
+    }
+}
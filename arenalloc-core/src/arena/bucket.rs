@@ -0,0 +1,624 @@
+use core::{
+    alloc::{Layout, LayoutError},
+    cell::Cell,
+    mem::{self, MaybeUninit},
+    ptr::NonNull,
+    slice,
+};
+
+use super::{sanitize, store::GlobalStore, BackingStore};
+use crate::compat;
+
+#[cfg(feature = "debug-poison")]
+use {super::poison, core::ptr};
+
+/// A Bucket is a bucket of bytes.
+/// These bytes may be the backing
+/// store of any type.
+///
+/// This is only the header of the allocation,
+/// the data of the Bucket directly follows it:
+/// ```skip
+/// [index, end, capacity, position, next, layout, data.....]
+/// ```
+/// The buckets of an Arena form a chain through `next`.
+/// Pointers into the data are always derived from
+/// the pointer returned by the allocator, never from
+/// a reference to the header, so they keep the
+/// provenance of the whole allocation.
+#[repr(C)]
+struct BucketImpl {
+    /// An index into the data field.
+    /// This index wil *always* index
+    /// the next free byte
+    index: Cell<usize>,
+
+    /// The first byte allocated from the back of the data
+    /// field, or the capacity if nothing was. Allocations
+    /// from the front never reach past it.
+    end: Cell<usize>,
+
+    /// The number of bytes in the data field.
+    capacity: usize,
+
+    /// The number of buckets before this one in the chain.
+    position: Cell<usize>,
+
+    /// The bucket after this one in the chain.
+    next: Cell<Option<NonNull<BucketImpl>>>,
+
+    /// The layout the Bucket was allocated with,
+    /// so freeing it never has to compute it again.
+    /// `None` if the Bucket is in memory the Arena doesn't own.
+    layout: Option<Layout>,
+}
+
+/// The alignment of the data of every bucket.
+/// The header is a multiple of its own alignment.
+pub(super) const DATA_ALIGN: usize = mem::align_of::<BucketImpl>();
+
+impl BucketImpl {
+    fn header_layout() -> Layout {
+        Layout::new::<Self>()
+    }
+
+    /// Returns the layout for an array with the size of `size`.
+    /// A MaybeUninit is used to be able to write padding bytes.
+    fn data_layout(size: usize) -> Result<Layout, LayoutError> {
+        Layout::array::<MaybeUninit<u8>>(size)
+    }
+
+    /// Returns a layout for a Node where the length of the data field is `size`.
+    /// This relies on the two functions defined above.
+    fn layout_from_size(size: usize) -> Result<Layout, LayoutError> {
+        let layout = Self::header_layout().extend(Self::data_layout(size)?)?.0;
+        Ok(layout.pad_to_align())
+    }
+
+    /// The offset of the data field from the start of the allocation.
+    fn data_offset() -> usize {
+        mem::size_of::<Self>()
+    }
+}
+
+impl BucketImpl {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[cfg(test)]
+    fn is_full(&self) -> bool {
+        self.index.get() == self.end.get()
+    }
+
+    /// Returns the number of bytes that
+    /// are still free in this bucket
+    fn remaining(&self) -> usize {
+        self.end.get() - self.index.get()
+    }
+}
+
+/// Represents an insufficient capacity
+/// within a Bucket
+#[derive(Debug)]
+pub struct CapacityError;
+
+#[derive(Debug)]
+pub struct RawAllocError;
+
+impl BucketImpl {
+    fn alloc_raw(
+        layout: Layout,
+        zeroed: bool,
+        store: &dyn BackingStore,
+    ) -> Result<*mut u8, RawAllocError> {
+        let ptr = if zeroed {
+            store.allocate_zeroed(layout)
+        } else {
+            store.allocate(layout)
+        };
+
+        ptr.map(NonNull::as_ptr).ok_or(RawAllocError)
+    }
+
+    /// # Safety
+    /// `this` must have been allocated by `store`,
+    /// and must not be used afterwards.
+    unsafe fn dealloc_raw(this: NonNull<Self>, store: &dyn BackingStore) {
+        let raw = RawBucket { ptr: this };
+        sanitize::unpoison(raw.data(), raw.capacity());
+
+        if let Some(layout) = this.as_ref().layout {
+            store.deallocate(this.cast(), layout);
+        }
+    }
+}
+
+/// An owned Bucket, which is freed when dropped
+/// unless it is handed to a chain with `into_raw`.
+pub(crate) struct Bucket<'s> {
+    ptr: NonNull<BucketImpl>,
+
+    /// Where the Bucket was allocated.
+    store: &'s dyn BackingStore,
+}
+
+impl<'s> Bucket<'s> {
+    /// Allocates a Bucket in `store` and returns it.
+    /// The data is zeroed if `zeroed` is true.
+    pub(super) fn new(
+        size: usize,
+        zeroed: bool,
+        store: &'s dyn BackingStore,
+    ) -> Result<Self, RawAllocError> {
+        let layout = BucketImpl::layout_from_size(size).map_err(|_| RawAllocError)?;
+
+        unsafe {
+            let ptr = BucketImpl::alloc_raw(layout, zeroed, store)?.cast::<BucketImpl>();
+
+            ptr.write(BucketImpl {
+                index: Cell::new(0),
+                end: Cell::new(size),
+                capacity: size,
+                position: Cell::new(0),
+                next: Cell::new(None),
+                layout: Some(layout),
+            });
+
+            let bucket = Self {
+                ptr: NonNull::new_unchecked(ptr),
+                store,
+            };
+            sanitize::poison(bucket.raw().data(), size);
+            Ok(bucket)
+        }
+    }
+
+    /// Puts a Bucket in the `len` bytes at `ptr`, or returns `None`
+    /// if they are too small for the header. Dropping or deallocating
+    /// it does nothing.
+    ///
+    /// # Safety
+    /// The bytes must be valid for writes, and not be
+    /// used otherwise for as long as the Bucket is.
+    pub(super) unsafe fn in_buffer(ptr: NonNull<u8>, len: usize) -> Option<Self> {
+        let padding = compat::addr(ptr.as_ptr()).wrapping_neg() & (DATA_ALIGN - 1);
+        let capacity = len
+            .checked_sub(padding)?
+            .checked_sub(BucketImpl::data_offset())?;
+
+        let ptr = ptr.as_ptr().add(padding).cast::<BucketImpl>();
+
+        ptr.write(BucketImpl {
+            index: Cell::new(0),
+            end: Cell::new(capacity),
+            capacity,
+            position: Cell::new(0),
+            next: Cell::new(None),
+            layout: None,
+        });
+
+        let bucket = Self {
+            ptr: NonNull::new_unchecked(ptr),
+            store: &GlobalStore,
+        };
+        sanitize::poison(bucket.raw().data(), capacity);
+        Some(bucket)
+    }
+
+    /// Gives up ownership, the Bucket has to
+    /// be freed with `RawBucket::dealloc`.
+    pub(super) fn into_raw(self) -> RawBucket {
+        let raw = self.raw();
+        mem::forget(self);
+        raw
+    }
+
+    #[cfg(test)]
+    pub(super) fn is_full(&self) -> bool {
+        unsafe { self.ptr.as_ref().is_full() }
+    }
+
+    #[cfg(test)]
+    pub(super) fn malloc<T>(&self, size: usize) -> Result<*mut T, CapacityError> {
+        let size = mem::size_of::<T>() * size;
+        let ptr = unsafe { self.raw().malloc_bytes(size, mem::align_of::<T>())? };
+        Ok(ptr as *mut T)
+    }
+
+    /// Returns a non-owning handle to this Bucket.
+    pub(super) fn raw(&self) -> RawBucket {
+        RawBucket { ptr: self.ptr }
+    }
+}
+
+/// A non-owning handle to a Bucket.
+/// The methods are only safe to call while
+/// the Bucket it was created from is alive.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(super) struct RawBucket {
+    ptr: NonNull<BucketImpl>,
+}
+
+impl RawBucket {
+    fn header(&self) -> &BucketImpl {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    pub(super) unsafe fn capacity(self) -> usize {
+        self.header().capacity()
+    }
+
+    pub(super) unsafe fn remaining(self) -> usize {
+        self.header().remaining()
+    }
+
+    /// The index of the next free byte.
+    pub(super) unsafe fn index(self) -> usize {
+        self.header().index.get()
+    }
+
+    /// The first byte allocated from the back.
+    pub(super) unsafe fn end(self) -> usize {
+        self.header().end.get()
+    }
+
+    /// The number of bytes handed out from the front and the back.
+    pub(super) unsafe fn used(self) -> usize {
+        self.index() + self.capacity() - self.end()
+    }
+
+    /// The number of buckets before this one in the chain.
+    pub(super) unsafe fn position(self) -> usize {
+        self.header().position.get()
+    }
+
+    /// The bucket after this one in the chain.
+    pub(super) unsafe fn next(self) -> Option<RawBucket> {
+        self.header().next.get().map(|ptr| RawBucket { ptr })
+    }
+
+    /// Links `bucket` into the chain right after this one,
+    /// and renumbers the buckets after it.
+    ///
+    /// # Safety
+    /// `bucket` must not be in a chain yet.
+    pub(super) unsafe fn insert_after(self, bucket: RawBucket) {
+        bucket.header().next.set(self.header().next.get());
+        self.header().next.set(Some(bucket.ptr));
+
+        let mut position = self.position();
+        let mut next = Some(bucket);
+
+        while let Some(bucket) = next {
+            position += 1;
+            bucket.header().position.set(position);
+            next = bucket.next();
+        }
+    }
+
+    /// Unlinks the buckets after this one from the chain,
+    /// and returns the first of them.
+    pub(super) unsafe fn split_off(self) -> Option<RawBucket> {
+        self.header().next.take().map(|ptr| RawBucket { ptr })
+    }
+
+    /// Frees the Bucket, unless it is in memory the Arena doesn't own.
+    ///
+    /// # Safety
+    /// The Bucket must have been created by `Bucket::into_raw` from
+    /// a Bucket allocated by `store`, and must not be used afterwards.
+    pub(super) unsafe fn dealloc(self, store: &dyn BackingStore) {
+        BucketImpl::dealloc_raw(self.ptr, store);
+    }
+
+    /// Returns the bytes handed out so far, `0..index`.
+    ///
+    /// Alignment gaps between allocations are zero if the bucket
+    /// was zeroed when it was allocated, unless the bytes were
+    /// rewound and handed out again. Padding inside the values
+    /// may be uninitialized.
+    ///
+    /// # Safety
+    /// Nothing may hold a mutable reference into those bytes
+    /// while the slice is alive, and the Bucket must outlive it.
+    pub(super) unsafe fn as_initialized_slice<'a>(self) -> &'a [MaybeUninit<u8>] {
+        let data = self.data() as *const MaybeUninit<u8>;
+        slice::from_raw_parts(data, self.index())
+    }
+
+    /// Returns the offset of `ptr` from the start of the data
+    /// field, if it points into the data field or just past it.
+    #[cfg(feature = "debug-poison")]
+    pub(super) fn offset_in<T>(self, ptr: *const T) -> Option<usize> {
+        let offset = compat::addr(ptr).checked_sub(compat::addr(self.data()))?;
+        (offset <= unsafe { self.capacity() }).then_some(offset)
+    }
+
+    /// Gives back every byte after `index`, making
+    /// that the next free byte again.
+    /// The bytes are poisoned with the `debug-poison` feature.
+    pub(super) unsafe fn rewind_to(self, index: usize) {
+        let current = self.header().index.get();
+        debug_assert!(index <= current);
+
+        #[cfg(feature = "debug-poison")]
+        ptr::write_bytes(self.data().add(index), poison::FREE_POISON, current - index);
+
+        sanitize::poison(self.data().add(index), current - index);
+        self.header().index.set(index);
+    }
+
+    /// Resizes the allocation of `old` bytes at `ptr` to `new` bytes,
+    /// if it is the last allocation of this bucket and there is room.
+    /// Returns whether the allocation was resized.
+    pub(super) unsafe fn resize_last(self, ptr: *mut u8, old: usize, new: usize) -> bool {
+        // Wraps around for pointers into other buckets,
+        // which then never end at `index`.
+        let start = compat::addr(ptr).wrapping_sub(compat::addr(self.data()));
+
+        if start.checked_add(old) != Some(self.index()) || new > self.end() - start {
+            return false;
+        }
+
+        if new < old {
+            self.rewind_to(start + new);
+        } else {
+            sanitize::unpoison(ptr.add(old), new - old);
+
+            #[cfg(feature = "debug-poison")]
+            ptr::write_bytes(ptr.add(old), poison::ALLOC_POISON, new - old);
+
+            self.header().index.set(start + new);
+        }
+        true
+    }
+
+    /// Returns a pointer to the start of the data field,
+    /// derived from the pointer to the whole allocation.
+    fn data(self) -> *mut u8 {
+        unsafe { (self.ptr.as_ptr() as *mut u8).add(BucketImpl::data_offset()) }
+    }
+
+    /// Returns the *next* index that has the correct
+    /// alignment in memory for `align`, a power of two.
+    #[inline]
+    fn align_index(self, align: usize) -> usize {
+        debug_assert!(align.is_power_of_two());
+
+        let data_addr = compat::addr(self.data());
+        let start_addr = data_addr + self.header().index.get();
+        let aligned_start = (start_addr + (align - 1)) & !(align - 1);
+        aligned_start - data_addr
+    }
+
+    /// Allocates `size` bytes, aligned to `align`.
+    /// `align` must be a power of two.
+    /// ```skip
+    /// [.., .., 0, 0, 0, 0, 0]
+    ///          ^
+    ///         index
+    ///
+    /// malloc_bytes(3, 1) results in:
+    /// [.., .., 0, 0, 0, 0, 0]
+    ///                   ^
+    ///                 index
+    /// ```
+    #[inline]
+    pub(super) unsafe fn malloc_bytes(
+        self,
+        size: usize,
+        align: usize,
+    ) -> Result<*mut u8, CapacityError> {
+        let start = self.align_index(align);
+
+        let end = start.saturating_add(size);
+        if end > self.end() {
+            return Err(CapacityError);
+        }
+
+        let ptr = self.data().add(start);
+        debug_assert_eq!(compat::addr(ptr) % align, 0);
+
+        // The padding before `ptr` is handed out as well.
+        let index = self.index();
+        sanitize::unpoison(self.data().add(index), end - index);
+
+        #[cfg(feature = "debug-poison")]
+        ptr::write_bytes(ptr, poison::ALLOC_POISON, size);
+
+        self.header().index.set(end);
+        Ok(ptr)
+    }
+}
+
+impl RawBucket {
+    /// Allocates `size` bytes from the back, aligned to `align`.
+    /// `align` must be a power of two.
+    /// ```skip
+    /// [.., .., 0, 0, 0, 0, 0, ..]
+    ///                         ^
+    ///                        end
+    ///
+    /// malloc_back(3, 1) results in:
+    /// [.., .., 0, 0, 0, 0, 0, ..]
+    ///                ^
+    ///               end
+    /// ```
+    pub(super) unsafe fn malloc_back(
+        self,
+        size: usize,
+        align: usize,
+    ) -> Result<*mut u8, CapacityError> {
+        let data_addr = compat::addr(self.data());
+
+        let start = (data_addr + self.end())
+            .checked_sub(size)
+            .map(|addr| addr & !(align - 1))
+            .and_then(|addr| addr.checked_sub(data_addr))
+            .filter(|&start| start >= self.index())
+            .ok_or(CapacityError)?;
+
+        let ptr = self.data().add(start);
+        sanitize::unpoison(ptr, self.end() - start);
+
+        #[cfg(feature = "debug-poison")]
+        ptr::write_bytes(ptr, poison::ALLOC_POISON, size);
+
+        self.header().end.set(start);
+        Ok(ptr)
+    }
+
+    /// Gives back every byte allocated from the back before `end`.
+    /// The bytes are poisoned with the `debug-poison` feature.
+    pub(super) unsafe fn rewind_back_to(self, end: usize) {
+        let current = self.end();
+        debug_assert!(current <= end && end <= self.capacity());
+
+        #[cfg(feature = "debug-poison")]
+        ptr::write_bytes(self.data().add(current), poison::FREE_POISON, end - current);
+
+        sanitize::poison(self.data().add(current), end - current);
+        self.header().end.set(end);
+    }
+}
+
+impl Drop for Bucket<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            BucketImpl::dealloc_raw(self.ptr, self.store);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bucket, GlobalStore};
+
+    #[test]
+    fn test_malloc() {
+        let b = Bucket::new(12, true, &GlobalStore).unwrap();
+        let _ptr = b.malloc::<u8>(1).unwrap();
+
+        let _otherptr = b.malloc::<u32>(2).unwrap();
+
+        assert!(b.is_full());
+        assert!(b.malloc::<u8>(1).is_err());
+    }
+
+    #[test]
+    fn test_write_multiple() {
+        let b = Bucket::new(64, true, &GlobalStore).unwrap();
+
+        let a = b.malloc::<u32>(1).unwrap();
+        let c = b.malloc::<u64>(2).unwrap();
+
+        unsafe {
+            a.write(1);
+            c.write(2);
+            c.add(1).write(3);
+
+            let a_ref = &mut *a;
+            let c_ref = &mut *c;
+            *a_ref += 1;
+            *c_ref += 1;
+
+            assert_eq!(a.read(), 2);
+            assert_eq!(c.read(), 3);
+            assert_eq!(c.add(1).read(), 3);
+        }
+    }
+
+    #[test]
+    fn test_malloc_back() {
+        let b = Bucket::new(16, true, &GlobalStore).unwrap();
+        let raw = b.raw();
+
+        unsafe {
+            let back = raw.malloc_back(3, 1).unwrap();
+            let front = b.malloc::<u32>(1).unwrap();
+            assert_eq!(raw.end(), 13);
+
+            let aligned = raw.malloc_back(4, 4).unwrap();
+            assert_eq!(raw.end(), 8);
+            assert_eq!(crate::compat::addr(aligned) % 4, 0);
+            assert!((front as *mut u8) < aligned && aligned < back);
+
+            assert!(b.malloc::<u32>(2).is_err());
+            assert!(raw.malloc_back(5, 1).is_err());
+            b.malloc::<u32>(1).unwrap();
+            assert!(b.is_full());
+
+            raw.rewind_back_to(16);
+            assert_eq!(raw.remaining(), 8);
+        }
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let (a, b, c) = (
+            Bucket::new(8, true, &GlobalStore).unwrap(),
+            Bucket::new(8, true, &GlobalStore).unwrap(),
+            Bucket::new(8, true, &GlobalStore).unwrap(),
+        );
+
+        unsafe {
+            a.raw().insert_after(c.raw());
+            a.raw().insert_after(b.raw());
+
+            assert!(a.raw().next() == Some(b.raw()));
+            assert!(b.raw().next() == Some(c.raw()));
+            assert!(c.raw().next().is_none());
+            assert_eq!(
+                [a.raw().position(), b.raw().position(), c.raw().position()],
+                [0, 1, 2]
+            );
+        }
+    }
+
+    #[test]
+    fn test_drop_while_unwinding() {
+        use crate::arena::Arena;
+        use std::panic;
+
+        let result = panic::catch_unwind(|| {
+            let arena = Arena::new();
+
+            arena.region(|s| {
+                s.malloc::<u64>(1000).unwrap();
+                panic!("boom");
+            })
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-poison")]
+    #[cfg_attr(
+        all(feature = "sanitizers", arenalloc_asan),
+        ignore = "reads memory given back"
+    )]
+    fn test_poison() {
+        use super::poison::{ALLOC_POISON, FREE_POISON};
+
+        let b = Bucket::new(64, true, &GlobalStore).unwrap();
+        let raw = b.raw();
+
+        let ptr = b.malloc::<u32>(4).unwrap();
+        unsafe {
+            assert_eq!(*(ptr as *const [u8; 16]), [ALLOC_POISON; 16]);
+
+            ptr.write(1);
+            raw.rewind_to(0);
+
+            assert_eq!(*(ptr as *const [u8; 16]), [FREE_POISON; 16]);
+
+            let back = raw.malloc_back(16, 1).unwrap();
+            assert_eq!(*(back as *const [u8; 16]), [ALLOC_POISON; 16]);
+
+            raw.rewind_back_to(64);
+            assert_eq!(*(back as *const [u8; 16]), [FREE_POISON; 16]);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use core::fmt;
+
+use alloc::rc::Rc;
+
+use super::{store, AllocError, Arena, BackingStore};
+
+/// The size of the first bucket, unless configured otherwise.
+pub(super) const INITIAL_CAPACITY: usize = 512;
+
+/// How large every new bucket is, given the size of the previous one.
+/// A bucket is never smaller than the allocation it is created for.
+#[derive(Debug, Copy, Clone)]
+pub enum Growth {
+    /// Twice as large as the previous bucket.
+    Doubling,
+
+    /// The previous size times `num / den`, which is larger than 1.
+    Factor { num: usize, den: usize },
+
+    /// Always the same size.
+    Fixed(usize),
+
+    /// Computed from the previous size by the function.
+    Custom(fn(usize) -> usize),
+}
+
+impl Growth {
+    /// Returns the size of the bucket after one of `size` bytes.
+    pub(super) fn next_size(self, size: usize) -> usize {
+        match self {
+            Growth::Doubling => Self::scale(size, 2, 1),
+            Growth::Factor { num, den } => Self::scale(size, num, den),
+            Growth::Fixed(size) => size,
+            Growth::Custom(f) => f(size),
+        }
+    }
+
+    /// Returns `size * num / den`, saturating at `usize::MAX`.
+    /// The result is always larger than `size`.
+    fn scale(size: usize, num: usize, den: usize) -> usize {
+        // Splitting `size` avoids overflowing `size * num`.
+        let whole = (size / den).saturating_mul(num);
+        let rest = (size % den).saturating_mul(num) / den;
+
+        whole.saturating_add(rest).max(size.saturating_add(1))
+    }
+}
+
+/// Configures and builds an [`Arena`].
+/// ```
+/// use arenalloc_core::arena::{Arena, Growth};
+///
+/// let arena = Arena::builder()
+///     .initial_capacity(64 * 1024)
+///     .growth(Growth::Fixed(64 * 1024))
+///     .build();
+///
+/// assert_eq!(arena.capacity(), 64 * 1024);
+/// ```
+#[derive(Clone)]
+pub struct ArenaBuilder {
+    pub(super) growth: Growth,
+    pub(super) initial_capacity: usize,
+    pub(super) limit: Option<usize>,
+    pub(super) first_fit: bool,
+    pub(super) zeroed: bool,
+    pub(super) store: Option<Rc<dyn BackingStore>>,
+}
+
+impl ArenaBuilder {
+    pub fn new() -> Self {
+        Self {
+            growth: Growth::Doubling,
+            initial_capacity: INITIAL_CAPACITY,
+            limit: None,
+            first_fit: false,
+            zeroed: true,
+            store: None,
+        }
+    }
+
+    /// Sets the size of the first bucket, 512 bytes by default.
+    /// It is capped at the limit, if there is one.
+    pub fn initial_capacity(mut self, bytes: usize) -> Self {
+        self.initial_capacity = bytes;
+        self
+    }
+
+    /// Sets how large new buckets are, [`Growth::Doubling`] by default.
+    ///
+    /// # Panics
+    /// Panics if a [`Growth::Factor`] is not larger than 1.
+    pub fn growth(mut self, growth: Growth) -> Self {
+        if let Growth::Factor { num, den } = growth {
+            assert!(
+                den > 0 && num > den,
+                "growth factor {}/{} must be larger than 1",
+                num,
+                den
+            );
+        }
+
+        self.growth = growth;
+        self
+    }
+
+    /// Sets the factor by which every new bucket is
+    /// larger than the previous one to `num / den`.
+    /// Short for `growth(Growth::Factor { num, den })`.
+    ///
+    /// # Panics
+    /// Panics if the factor is not larger than 1.
+    pub fn growth_factor(self, num: usize, den: usize) -> Self {
+        self.growth(Growth::Factor { num, den })
+    }
+
+    /// Sets a hard cap on the bytes the buckets reserve together.
+    /// Growing past it fails with [`AllocError::LimitExceeded`],
+    /// for every kind of allocation. The headers of the buckets are
+    /// not counted. See also [`Arena::with_limit`].
+    #[doc(alias = "max_bytes")]
+    pub fn limit(mut self, max_bytes: usize) -> Self {
+        self.limit = Some(max_bytes);
+        self
+    }
+
+    /// When the current bucket is full, first tries the earlier buckets
+    /// before growing. That wastes less memory when large allocations
+    /// leave earlier buckets partly empty, at the cost of slower growth.
+    /// Off by default.
+    ///
+    /// Only buckets used since the innermost open region started are
+    /// tried, so regions still give back everything. Rewinding to a
+    /// [`Marker`](super::Marker) may not give back what was allocated
+    /// into buckets before it.
+    pub fn first_fit(mut self, enabled: bool) -> Self {
+        self.first_fit = enabled;
+        self
+    }
+
+    /// Whether new buckets are zeroed when they are allocated,
+    /// which is on by default. Turning it off saves writing every
+    /// byte of a bucket up front, and allocations are uninitialized
+    /// either way. Use [`Scope::alloc_zeroed_slice`](super::Scope::alloc_zeroed_slice)
+    /// for memory that has to be zeroed.
+    pub fn zeroed(mut self, enabled: bool) -> Self {
+        self.zeroed = enabled;
+        self
+    }
+
+    /// Allocates the buckets in `store` instead of with the global
+    /// allocator. Buckets are only ever given back to the store
+    /// they were allocated by.
+    pub fn backing_store(mut self, store: impl BackingStore + 'static) -> Self {
+        self.store = Some(Rc::new(store));
+        self
+    }
+
+    pub(super) fn store(&self) -> &dyn BackingStore {
+        store::or_global(self.store.as_ref())
+    }
+
+    /// # Panics
+    /// Panics if the first bucket could not be allocated.
+    pub fn build(self) -> Arena {
+        self.try_build().expect("Allocation failed")
+    }
+
+    /// Like [`ArenaBuilder::build`], but returns an error
+    /// if the first bucket could not be allocated.
+    pub fn try_build(self) -> Result<Arena, AllocError> {
+        Arena::with_config(self)
+    }
+}
+
+impl fmt::Debug for ArenaBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaBuilder")
+            .field("growth", &self.growth)
+            .field("initial_capacity", &self.initial_capacity)
+            .field("limit", &self.limit)
+            .field("first_fit", &self.first_fit)
+            .field("zeroed", &self.zeroed)
+            .field("custom_store", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl Default for ArenaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Growth;
+
+    #[test]
+    fn test_next_size() {
+        let factor = Growth::Factor { num: 3, den: 2 };
+
+        assert_eq!(factor.next_size(512), 768);
+        assert_eq!(factor.next_size(1), 2);
+        assert_eq!(factor.next_size(usize::MAX / 3 * 2), usize::MAX);
+        assert_eq!(factor.next_size(usize::MAX), usize::MAX);
+
+        assert_eq!(Growth::Doubling.next_size(512), 1024);
+        assert_eq!(Growth::Fixed(100).next_size(512), 100);
+        assert_eq!(Growth::Custom(|size| size + 10).next_size(512), 522);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_factor_of_one() {
+        super::ArenaBuilder::new().growth_factor(4, 4);
+    }
+}
@@ -0,0 +1,255 @@
+use core::{cell::RefCell, mem, ptr};
+
+use alloc::vec::Vec;
+
+use super::{AllocError, Scope};
+
+/// A value in the arena, and the destructor of its type.
+struct ErasedDrop {
+    value: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+/// Drops the value of type `T` behind `value`.
+unsafe fn drop_value<T>(value: *mut ()) {
+    ptr::drop_in_place(value as *mut T)
+}
+
+/// The values registered by [`Scope::alloc_with_drop`] and
+/// [`Scope::drop_later`], in the order they were registered.
+pub(super) struct DropRegistry {
+    drops: RefCell<Vec<ErasedDrop>>,
+}
+
+impl DropRegistry {
+    pub(super) const fn new() -> Self {
+        Self {
+            drops: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.drops.borrow().len()
+    }
+
+    /// Drops the values registered after the first `len`, last first.
+    ///
+    /// If a destructor panics, the values before it stay registered.
+    ///
+    /// # Safety
+    /// The values must still be valid, and not be used afterwards.
+    pub(super) unsafe fn drop_from(&self, len: usize) {
+        // Not borrowed while a destructor runs, which may register more.
+        while let Some(erased) = self.pop_after(len) {
+            (erased.drop)(erased.value)
+        }
+    }
+
+    fn pop_after(&self, len: usize) -> Option<ErasedDrop> {
+        let mut drops = self.drops.borrow_mut();
+
+        if drops.len() > len {
+            drops.pop()
+        } else {
+            None
+        }
+    }
+}
+
+impl<'scope> Scope<'scope> {
+    /// Moves `value` into the arena, like [`Scope::alloc`], but
+    /// the value is dropped when the memory is given back: when the
+    /// region ends, or when the Arena is rewound, reset or dropped.
+    /// Values are dropped in the reverse order they were allocated.
+    ///
+    /// The value must be `'static`, as a destructor that could
+    /// see another value of the arena might see one that was
+    /// already dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use std::rc::Rc;
+    ///
+    /// let arena = Arena::new();
+    /// let shared = Rc::new(());
+    ///
+    /// arena.region(|s| {
+    ///     let copy = s.alloc_with_drop(Rc::clone(&shared));
+    ///     assert_eq!(Rc::strong_count(copy), 2);
+    /// });
+    ///
+    /// assert_eq!(Rc::strong_count(&shared), 1);
+    /// ```
+    ///
+    /// A value can't point to one allocated after it, which would
+    /// be dropped first:
+    /// ```compile_fail
+    /// use arenalloc_core::arena::Arena;
+    /// use std::cell::Cell;
+    ///
+    /// struct Node<'a>(Cell<Option<&'a Node<'a>>>, String);
+    ///
+    /// impl Drop for Node<'_> {
+    ///     fn drop(&mut self) {
+    ///         if let Some(next) = self.0.get() {
+    ///             println!("{}", next.1);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let first = s.alloc_with_drop(Node(Cell::new(None), String::from("first")));
+    ///     let second = s.alloc_with_drop(Node(Cell::new(None), String::from("second")));
+    ///     first.0.set(Some(second));
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_with_drop<T: 'static>(&self, value: T) -> &'scope mut T {
+        self.try_alloc_with_drop(value).expect("Allocation failed")
+    }
+
+    /// Like [`Scope::alloc_with_drop`], but returns an error
+    /// if the value could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc_with_drop<T: 'static>(&self, value: T) -> Result<&'scope mut T, AllocError> {
+        unsafe {
+            let ptr = self.malloc::<T>(1)?;
+            ptr.write(value);
+            self.drop_later(ptr);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Registers the value at `ptr` to be dropped when the memory is
+    /// given back, see [`Scope::alloc_with_drop`]. This is for values
+    /// written to memory from [`Scope::malloc`] or [`Scope::alloc_layout`].
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid `T` allocated
+    /// through this Scope, which nothing else drops.
+    /// `T` is `'static` for the reason given there.
+    pub unsafe fn drop_later<T: 'static>(&self, ptr: *mut T) {
+        if !mem::needs_drop::<T>() {
+            return;
+        }
+
+        self.arena().drops().drops.borrow_mut().push(ErasedDrop {
+            value: ptr as *mut (),
+            drop: drop_value::<T>,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::Arena;
+
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// Records its number in the log when it is dropped.
+    struct Noisy(Rc<RefCell<Vec<u32>>>, u32);
+
+    impl Drop for Noisy {
+        fn drop(&mut self) {
+            self.0.borrow_mut().push(self.1);
+        }
+    }
+
+    #[test]
+    fn test_region() {
+        let arena = Arena::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        arena.region(|s| {
+            s.alloc_with_drop(Noisy(log.clone(), 1));
+
+            arena.region(|inner| {
+                inner.alloc_with_drop(Noisy(log.clone(), 2));
+                inner.alloc_with_drop(Noisy(log.clone(), 3));
+            });
+            assert_eq!(*log.borrow(), [3, 2]);
+
+            // Allocated for the outer region, so it is kept.
+            arena.region(|_| s.alloc_with_drop(Noisy(log.clone(), 4)));
+            assert_eq!(log.borrow().len(), 2);
+        });
+
+        assert_eq!(*log.borrow(), [3, 2, 4, 1]);
+    }
+
+    #[test]
+    fn test_drop_later() {
+        let arena = Arena::new();
+        let shared = Rc::new(());
+
+        arena.region(|s| {
+            let ptr = s.malloc::<Rc<()>>(2).unwrap();
+
+            unsafe {
+                for i in 0..2 {
+                    ptr.add(i).write(Rc::clone(&shared));
+                    s.drop_later(ptr.add(i));
+                }
+            }
+            assert_eq!(Rc::strong_count(&shared), 3);
+        });
+
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn test_reset_and_drop() {
+        let mut arena = Arena::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        for n in 0..2 {
+            // The ArenaBox keeps what the region allocated before it.
+            let kept = arena.region(|s| {
+                s.alloc_with_drop(Noisy(log.clone(), n));
+                arena.alloc(0u8)
+            });
+            drop(kept);
+            assert!(log.borrow().is_empty());
+        }
+
+        arena.reset();
+        assert_eq!(*log.borrow(), [1, 0]);
+
+        arena.region(|s| {
+            s.alloc_with_drop(Noisy(log.clone(), 2));
+            arena.alloc(0u8)
+        });
+        drop(arena);
+        assert_eq!(*log.borrow(), [1, 0, 2]);
+    }
+
+    #[test]
+    fn test_destructor_panics() {
+        struct Bomb;
+
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                panic!("boom");
+            }
+        }
+
+        let arena = Arena::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            arena.region(|s| {
+                s.alloc_with_drop(Noisy(log.clone(), 1));
+                s.alloc_with_drop(Bomb);
+            })
+        }));
+
+        assert!(result.is_err());
+        assert!(log.borrow().is_empty());
+
+        drop(arena);
+        assert_eq!(*log.borrow(), [1]);
+    }
+}
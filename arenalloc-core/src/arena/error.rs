@@ -0,0 +1,66 @@
+use core::fmt;
+
+use super::bucket::{CapacityError, RawAllocError};
+
+/// The error returned by fallible Arena
+/// operations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocError {
+    /// The size of the request does not fit in an
+    /// `isize`, so it can never be allocated.
+    Overflow,
+
+    /// The request did not fit in the
+    /// available buckets.
+    CapacityExceeded,
+
+    /// The system allocator failed to
+    /// allocate a new bucket.
+    SystemAllocFailed,
+
+    /// Growing the Arena would make its capacity
+    /// exceed the limit set by [`Arena::with_limit`].
+    ///
+    /// [`Arena::with_limit`]: super::Arena::with_limit
+    LimitExceeded {
+        /// The limit of the Arena, in bytes.
+        limit: usize,
+
+        /// The number of bytes that were needed.
+        requested: usize,
+    },
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::Overflow => f.write_str("the size of the allocation overflows"),
+            AllocError::CapacityExceeded => {
+                f.write_str("the allocation does not fit in the buckets")
+            }
+            AllocError::SystemAllocFailed => {
+                f.write_str("the system allocator failed to allocate a bucket")
+            }
+            AllocError::LimitExceeded { limit, requested } => write!(
+                f,
+                "allocating {} bytes would exceed the limit of {} bytes",
+                requested, limit
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocError {}
+
+impl From<CapacityError> for AllocError {
+    fn from(_: CapacityError) -> Self {
+        AllocError::CapacityExceeded
+    }
+}
+
+impl From<RawAllocError> for AllocError {
+    fn from(_: RawAllocError) -> Self {
+        AllocError::SystemAllocFailed
+    }
+}
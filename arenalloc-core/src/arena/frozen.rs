@@ -0,0 +1,160 @@
+use core::fmt;
+
+use super::Arena;
+
+/// An [`Arena`] that can't allocate anymore, made by [`Arena::freeze`].
+/// It can be shared by threads, to read what was built in it
+/// before, like a syntax tree or an index.
+///
+/// Values are found again through pointers from before the freeze,
+/// like those from [`ArenaBox::into_raw`](super::ArenaBox::into_raw).
+/// ```
+/// use arenalloc_core::arena::{Arena, ArenaBox};
+/// use std::thread;
+///
+/// struct Node<'a> {
+///     name: &'static str,
+///     children: &'a [&'a Node<'a>],
+/// }
+///
+/// let arena = Arena::new();
+/// let root = {
+///     let node = |name, children| unsafe {
+///         &*ArenaBox::into_raw(arena.alloc(Node { name, children }))
+///     };
+///     let leaves = unsafe { &*ArenaBox::into_raw(arena.alloc([node("a", &[]), node("b", &[])])) };
+///     node("root", leaves) as *const Node
+/// };
+///
+/// let frozen = arena.freeze();
+/// let root = unsafe { frozen.get(root) };
+///
+/// thread::scope(|threads| {
+///     for _ in 0..2 {
+///         threads.spawn(|| assert_eq!(root.children[1].name, "b"));
+///     }
+/// });
+/// ```
+pub struct FrozenArena {
+    arena: Arena,
+}
+
+// `Arena::freeze` makes sure that nothing in the arena but the
+// buckets is used by a thread, and only reading methods take `&self`.
+unsafe impl Send for FrozenArena {}
+unsafe impl Sync for FrozenArena {}
+
+impl Arena {
+    /// Forbids further allocation, so the arena can be shared
+    /// by threads. See [`FrozenArena`]. The values waiting to be
+    /// dropped, by [`Scope::alloc_with_drop`](super::Scope::alloc_with_drop)
+    /// inside a region that was kept, are dropped first. The
+    /// observer is removed.
+    ///
+    /// # Panics
+    /// Panics if the Arena has a [`BackingStore`](super::BackingStore)
+    /// of its own, which may not be usable by other threads.
+    pub fn freeze(mut self) -> FrozenArena {
+        assert!(
+            self.uses_global_store(),
+            "an Arena with a BackingStore of its own can't be frozen"
+        );
+
+        // No region is open, so no Scope can use them anymore.
+        unsafe { self.drops().drop_from(0) };
+        self.set_observer(None);
+
+        FrozenArena { arena: self }
+    }
+}
+
+impl FrozenArena {
+    /// Returns the value at `ptr`, for as long as
+    /// the FrozenArena is borrowed.
+    ///
+    /// # Safety
+    /// `ptr` must point to an initialized `T` that was allocated
+    /// in this arena before it was frozen, and is not dropped.
+    /// References in the value must point into the arena, or be `'static`.
+    pub unsafe fn get<T: ?Sized + Sync>(&self, ptr: *const T) -> &T {
+        &*ptr
+    }
+
+    /// Allows allocating again. Values stay where they are.
+    pub fn thaw(self) -> Arena {
+        self.arena
+    }
+
+    /// See [`Arena::allocated_bytes`].
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+
+    /// See [`Arena::capacity`].
+    pub fn capacity(&self) -> usize {
+        self.arena.capacity()
+    }
+}
+
+impl fmt::Debug for FrozenArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrozenArena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{Arena, ArenaBox, GlobalStore};
+
+    use alloc::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn test_share() {
+        let arena = Arena::new();
+        let arrays = [1, 2, 3].map(|n| ArenaBox::into_raw(arena.alloc([n as u8; 64])));
+        let used = arena.allocated_bytes();
+
+        let frozen = arena.freeze();
+        assert_eq!(frozen.allocated_bytes(), used);
+
+        let arrays = arrays.map(|array| unsafe { frozen.get(array) });
+        thread::scope(|threads| {
+            for (n, array) in (1..).zip(arrays) {
+                threads.spawn(move || {
+                    assert_eq!(array.iter().map(|&b| b as usize).sum::<usize>(), n * 64);
+                });
+            }
+        });
+
+        let arena = frozen.thaw();
+        arena.alloc(0u8);
+        assert_eq!(arena.allocated_bytes(), used + 1);
+    }
+
+    #[test]
+    fn test_drops_pending() {
+        let arena = Arena::new();
+        let shared = Rc::new(());
+
+        // An ArenaBox keeps what the region allocated before it.
+        let kept = arena.region(|s| {
+            s.alloc_with_drop(Rc::clone(&shared));
+            ArenaBox::into_raw(arena.alloc(1u32))
+        });
+        assert_eq!(Rc::strong_count(&shared), 2);
+
+        let frozen = arena.freeze();
+        assert_eq!(Rc::strong_count(&shared), 1);
+        assert_eq!(unsafe { *frozen.get(kept) }, 1);
+    }
+
+    #[test]
+    #[should_panic = "can't be frozen"]
+    fn test_own_store() {
+        Arena::builder().backing_store(GlobalStore).build().freeze();
+    }
+}
@@ -0,0 +1,240 @@
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::compat;
+
+/// A bump allocator over an inline buffer of `N` bytes,
+/// to be used as `#[global_allocator]` where there is no heap.
+///
+/// Deallocating does nothing, memory is never reused. The last
+/// allocation can be grown in place by `realloc`. When the
+/// buffer is full, allocating returns null. See
+/// [`ArenaGlobal::high_water_mark`] for how much was used.
+/// ```
+/// use arenalloc_core::arena::ArenaGlobal;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: ArenaGlobal<{ 1 << 20 }> = ArenaGlobal::new();
+///
+/// let v = vec![1u8; 100];
+/// assert!(ALLOCATOR.high_water_mark() >= 100);
+/// ```
+pub struct ArenaGlobal<const N: usize> {
+    memory: UnsafeCell<[MaybeUninit<u8>; N]>,
+
+    /// The number of bytes handed out, including padding.
+    /// It never goes down.
+    used: AtomicUsize,
+}
+
+// The bytes of `memory` are only ever handed out once.
+unsafe impl<const N: usize> Sync for ArenaGlobal<N> {}
+
+impl<const N: usize> ArenaGlobal<N> {
+    pub const fn new() -> Self {
+        Self {
+            memory: UnsafeCell::new([MaybeUninit::uninit(); N]),
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of bytes handed out so far, including padding.
+    /// As nothing is given back, this is the most that was ever in
+    /// use, and it only goes up. The buffer is exhausted when it
+    /// reaches [`ArenaGlobal::capacity`].
+    pub fn high_water_mark(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// The size of the buffer.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    fn data(&self) -> *mut u8 {
+        self.memory.get() as *mut u8
+    }
+
+    /// Returns the offset of `ptr` into the buffer.
+    fn offset_of(&self, ptr: *mut u8) -> usize {
+        compat::addr(ptr) - compat::addr(self.data())
+    }
+
+    /// Moves the end of the used bytes from `current` to `new`,
+    /// which is never before `current`. The bytes in between
+    /// were never handed out, so no ordering is needed.
+    fn bump(&self, current: usize, new: usize) -> Result<(), usize> {
+        debug_assert!(new >= current);
+        self.used
+            .compare_exchange(current, new, Ordering::Relaxed, Ordering::Relaxed)
+            .map(drop)
+    }
+}
+
+impl<const N: usize> Default for ArenaGlobal<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<const N: usize> GlobalAlloc for ArenaGlobal<N> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let data = compat::addr(self.data());
+        let mut used = self.used.load(Ordering::Relaxed);
+
+        loop {
+            // `data + used` can't overflow, it is inside the buffer.
+            let start = match (data + used).checked_add(layout.align() - 1) {
+                Some(end) => (end & !(layout.align() - 1)) - data,
+                None => return ptr::null_mut(),
+            };
+
+            let end = match start.checked_add(layout.size()) {
+                Some(end) if end <= N => end,
+                _ => return ptr::null_mut(),
+            };
+
+            match self.bump(used, end) {
+                Ok(()) => return self.data().add(start),
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, _: *mut u8, _: Layout) {}
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size <= layout.size() {
+            return ptr;
+        }
+
+        // Grows in place if it is the last allocation.
+        let start = self.offset_of(ptr);
+        if let Some(new_end) = start.checked_add(new_size).filter(|&end| end <= N) {
+            if self.bump(start + layout.size(), new_end).is_ok() {
+                return ptr;
+            }
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new = self.alloc(new_layout);
+
+        if !new.is_null() {
+            ptr::copy_nonoverlapping(ptr, new, layout.size().min(new_size));
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaGlobal;
+    use crate::compat;
+
+    use core::alloc::{GlobalAlloc, Layout};
+    use std::{sync::Barrier, thread, vec::Vec};
+
+    #[test]
+    fn test_high_water_mark() {
+        let arena = ArenaGlobal::<256>::new();
+
+        unsafe {
+            let a = arena.alloc(Layout::new::<u8>());
+            let b = arena.alloc(Layout::new::<u64>());
+            assert_eq!(compat::addr(b) % 8, 0);
+            let used = arena.high_water_mark();
+            assert_eq!(used, 16);
+
+            arena.dealloc(a, Layout::new::<u8>());
+            assert_eq!(arena.high_water_mark(), used);
+
+            let c = arena.realloc(b, Layout::new::<u64>(), 64);
+            assert_eq!(c, b);
+            assert_eq!(arena.high_water_mark(), used + 56);
+
+            // Neither shrinking nor deallocating gives anything back.
+            let d = arena.realloc(c, Layout::from_size_align(64, 8).unwrap(), 8);
+            assert_eq!(d, c);
+            arena.dealloc(d, Layout::new::<u64>());
+            assert_eq!(arena.high_water_mark(), used + 56);
+
+            let e = arena.alloc(Layout::new::<u64>());
+            assert_eq!(arena.offset_of(e), used + 56);
+
+            assert!(arena.alloc(Layout::new::<[u8; 512]>()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_realloc_moves() {
+        let arena = ArenaGlobal::<256>::new();
+
+        unsafe {
+            let a = arena.alloc(Layout::new::<[u8; 4]>());
+            a.copy_from([1, 2, 3, 4].as_ptr(), 4);
+            arena.alloc(Layout::new::<u8>());
+
+            let b = arena.realloc(a, Layout::new::<[u8; 4]>(), 8);
+            assert_ne!(a, b);
+            assert_eq!(*(b as *const [u8; 4]), [1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        static ARENA: ArenaGlobal<{ 64 * 1024 }> = ArenaGlobal::new();
+        let barrier = Barrier::new(4);
+
+        thread::scope(|scope| {
+            for n in 0..4u8 {
+                let barrier = &barrier;
+
+                scope.spawn(move || {
+                    let ptrs = (0..100)
+                        .map(|_| unsafe {
+                            let ptr = ARENA.alloc(Layout::new::<[u8; 16]>());
+                            ptr.write_bytes(n, 16);
+                            ptr
+                        })
+                        .collect::<Vec<_>>();
+
+                    barrier.wait();
+
+                    for ptr in ptrs {
+                        assert_eq!(unsafe { *(ptr as *const [u8; 16]) }, [n; 16]);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(ARENA.high_water_mark(), 4 * 100 * 16);
+    }
+
+    #[test]
+    fn test_threads_dealloc() {
+        static ARENA: ArenaGlobal<{ 8 * 1024 }> = ArenaGlobal::new();
+        let layout = Layout::new::<[u8; 16]>();
+
+        thread::scope(|scope| {
+            for n in 0..2u8 {
+                scope.spawn(move || {
+                    for _ in 0..100 {
+                        unsafe {
+                            let ptr = ARENA.alloc(layout);
+                            ptr.write_bytes(n, 16);
+                            assert_eq!(*(ptr as *const [u8; 16]), [n; 16]);
+                            ARENA.dealloc(ptr, layout);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(ARENA.high_water_mark(), 2 * 100 * 16);
+    }
+}
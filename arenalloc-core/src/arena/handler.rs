@@ -0,0 +1,223 @@
+use core::{cell::RefCell, marker::PhantomData};
+
+use alloc::vec::Vec;
+
+use super::Scope;
+
+/// Identifies a handler registered with [`Scope::alloc_handler`].
+/// It can only be used with the Scope it was created by.
+pub struct HandlerId<'scope, E> {
+    index: usize,
+    brand: PhantomData<*mut &'scope ()>,
+    event: PhantomData<fn(&E)>,
+}
+
+impl<E> Clone for HandlerId<'_, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for HandlerId<'_, E> {}
+
+/// A closure in the arena, with its type erased.
+#[derive(Copy, Clone)]
+struct ErasedHandler {
+    closure: *const (),
+    call: unsafe fn(*const (), *const ()),
+}
+
+/// Calls the closure of type `F` behind `closure`
+/// with the event of type `E` behind `event`.
+unsafe fn call<F, E>(closure: *const (), event: *const ())
+where
+    F: Fn(&E),
+{
+    let closure = &*(closure as *const F);
+    closure(&*(event as *const E))
+}
+
+/// The handlers of all Scopes of an Arena.
+pub(super) struct HandlerRegistry {
+    handlers: RefCell<Vec<ErasedHandler>>,
+}
+
+impl HandlerRegistry {
+    pub(super) const fn new() -> Self {
+        Self {
+            handlers: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.handlers.borrow().len()
+    }
+
+    /// Forgets the handlers registered after the first `len`.
+    pub(super) fn truncate(&self, len: usize) {
+        self.handlers.borrow_mut().truncate(len);
+    }
+}
+
+impl<'scope> Scope<'scope> {
+    /// Moves the closure into the arena and registers it
+    /// as a handler, which can be called with [`Scope::invoke`].
+    /// The closure is never dropped.
+    ///
+    /// A closure that uses the Scope itself has to capture
+    /// a copy of it (`let s = *s;`), not the reference
+    /// passed to the region.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    /// use core::cell::Cell;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let clicks = s.alloc_ref(Cell::new(0));
+    ///     let on_click = s.alloc_handler(move |n: &u32| clicks.set(clicks.get() + n));
+    ///
+    ///     s.invoke(on_click, &2);
+    ///     s.invoke(on_click, &3);
+    ///
+    ///     assert_eq!(clicks.get(), 5);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_handler<E, F>(&self, f: F) -> HandlerId<'scope, E>
+    where
+        F: Fn(&E) + 'scope,
+    {
+        let closure = unsafe {
+            let ptr = self.malloc::<F>(1).expect("Allocation failed");
+            ptr.write(f);
+            ptr
+        };
+
+        let mut handlers = self.arena().handlers().handlers.borrow_mut();
+        handlers.push(ErasedHandler {
+            closure: closure as *const (),
+            call: call::<F, E>,
+        });
+
+        HandlerId {
+            index: handlers.len() - 1,
+            brand: PhantomData,
+            event: PhantomData,
+        }
+    }
+
+    /// Calls the handler identified by `id` with `event`.
+    /// Handlers may register and invoke other handlers.
+    pub fn invoke<E>(&self, id: HandlerId<'scope, E>, event: &E) {
+        let handler = self.arena().handlers().handlers.borrow()[id.index];
+
+        // `id` was created by `alloc_handler` with a closure taking an `E`,
+        // and the closure lives as long as the Scope.
+        unsafe { (handler.call)(handler.closure, event as *const E as *const ()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{Arena, ArenaRef};
+
+    use core::cell::{Cell, RefCell};
+
+    enum Event {
+        Click,
+        Key(char),
+    }
+
+    struct Node<'scope> {
+        name: &'static str,
+        parent: Option<ArenaRef<'scope, Node<'scope>>>,
+        clicks: Cell<usize>,
+    }
+
+    #[derive(Default)]
+    struct Log {
+        names: [&'static str; 8],
+        len: usize,
+    }
+
+    impl Log {
+        fn push(&mut self, name: &'static str) {
+            self.names[self.len] = name;
+            self.len += 1;
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let s = *s;
+            let log = s.alloc_ref(RefCell::new(Log::default()));
+
+            let root = s.alloc_ref(Node {
+                name: "root",
+                parent: None,
+                clicks: Cell::new(0),
+            });
+            let child = s.alloc_ref(Node {
+                name: "child",
+                parent: Some(root),
+                clicks: Cell::new(0),
+            });
+
+            let on_root = s.alloc_handler(move |event: &Event| {
+                if let Event::Click = event {
+                    root.clicks.set(root.clicks.get() + 1);
+                }
+                log.borrow_mut().push(root.name);
+            });
+
+            // Bubbles every event up to its parent.
+            let on_child = s.alloc_handler(move |event: &Event| {
+                if let Event::Key(c) = event {
+                    assert_eq!(*c, 'x');
+                    child.clicks.set(child.clicks.get() + 1);
+                }
+                log.borrow_mut().push(child.name);
+
+                if child.parent.is_some() {
+                    s.invoke(on_root, event);
+                }
+            });
+
+            s.invoke(on_child, &Event::Click);
+            s.invoke(on_child, &Event::Key('x'));
+            s.invoke(on_root, &Event::Click);
+
+            assert_eq!(root.clicks.get(), 2);
+            assert_eq!(child.clicks.get(), 1);
+
+            let log = log.borrow();
+            assert_eq!(
+                log.names[..log.len],
+                ["child", "root", "child", "root", "root"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_register_while_invoked() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let s = *s;
+            let hits = s.alloc_ref(Cell::new(0));
+
+            let outer = s.alloc_handler(move |_: &()| {
+                let inner = s.alloc_handler(move |_: &()| hits.set(hits.get() + 1));
+                s.invoke(inner, &());
+            });
+
+            s.invoke(outer, &());
+            s.invoke(outer, &());
+            assert_eq!(hits.get(), 2);
+        });
+    }
+}
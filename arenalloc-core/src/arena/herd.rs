@@ -0,0 +1,320 @@
+use core::{
+    alloc::Layout,
+    cell::{Cell, UnsafeCell},
+    mem,
+    ptr::{self, NonNull},
+    slice,
+    sync::atomic::AtomicBool,
+};
+
+use alloc::vec::Vec;
+
+use super::{
+    builder::INITIAL_CAPACITY,
+    sync::{SpinLock, SyncBucket},
+    AllocError,
+};
+use crate::compat;
+
+/// A set of arenas for allocating from many threads at once,
+/// without synchronizing every allocation.
+///
+/// Every thread takes a [`Member`] with [`Herd::get`], which
+/// allocates from buckets of its own. The buckets are owned by
+/// the Herd, so the values live as long as the Herd does and
+/// outlive the threads that allocated them. A dropped Member
+/// gives its buckets back, and a later Member continues in them.
+/// The values are never dropped.
+/// ```
+/// use arenalloc_core::arena::Herd;
+/// use std::thread;
+///
+/// let herd = Herd::new();
+/// let herd = &herd;
+///
+/// let squares = thread::scope(|scope| {
+///     let workers = (0..4u64)
+///         .map(|t| {
+///             scope.spawn(move || {
+///                 let member = herd.get();
+///                 &*member.alloc_slice_copy(&[t * t; 10])
+///             })
+///         })
+///         .collect::<Vec<_>>();
+///
+///     workers
+///         .into_iter()
+///         .map(|worker| worker.join().unwrap())
+///         .collect::<Vec<_>>()
+/// });
+///
+/// assert_eq!(squares[3], [9; 10]);
+/// ```
+pub struct Herd {
+    /// The buckets given back by dropped members,
+    /// each linking to the buckets before it.
+    idle: UnsafeCell<Vec<NonNull<SyncBucket>>>,
+
+    /// Held while `idle` is used.
+    lock: AtomicBool,
+
+    /// The capacity of the first bucket of a member.
+    capacity: usize,
+}
+
+// `idle` is only used while holding `lock`,
+// and a bucket is owned by one member at a time.
+unsafe impl Send for Herd {}
+unsafe impl Sync for Herd {}
+
+impl Herd {
+    pub fn new() -> Self {
+        Self::with_capacity(INITIAL_CAPACITY)
+    }
+
+    /// Creates a Herd whose members start with a bucket of `bytes`.
+    /// No memory is allocated until a member allocates.
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            idle: UnsafeCell::new(Vec::new()),
+            lock: AtomicBool::new(false),
+            capacity: bytes,
+        }
+    }
+
+    /// Returns a member to allocate from, which continues
+    /// in the buckets of a dropped member if there is one.
+    pub fn get(&self) -> Member<'_> {
+        let bucket = {
+            let _lock = SpinLock::acquire(&self.lock);
+            unsafe { (*self.idle.get()).pop() }
+        };
+
+        Member {
+            herd: self,
+            current: Cell::new(bucket.map_or(ptr::null_mut(), NonNull::as_ptr)),
+        }
+    }
+
+    /// The number of bytes handed out by all members,
+    /// including padding.
+    pub fn allocated_bytes(&mut self) -> usize {
+        self.idle
+            .get_mut()
+            .iter()
+            .map(|bucket| unsafe { SyncBucket::chain_used(bucket.as_ptr()) })
+            .sum()
+    }
+
+    /// The total number of bytes reserved by the buckets of all members.
+    pub fn capacity(&mut self) -> usize {
+        self.idle
+            .get_mut()
+            .iter()
+            .map(|bucket| unsafe { SyncBucket::chain_capacity(bucket.as_ptr()) })
+            .sum()
+    }
+
+    fn give_back(&self, bucket: NonNull<SyncBucket>) {
+        let _lock = SpinLock::acquire(&self.lock);
+        unsafe { (*self.idle.get()).push(bucket) }
+    }
+}
+
+impl Default for Herd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Herd {
+    fn drop(&mut self) {
+        for bucket in self.idle.get_mut().drain(..) {
+            unsafe { SyncBucket::free_chain(bucket.as_ptr()) }
+        }
+    }
+}
+
+/// Allocates from buckets of its own in a [`Herd`],
+/// see [`Herd::get`].
+///
+/// A Member can be sent to another thread, but not shared.
+/// The values it allocates live as long as the Herd.
+pub struct Member<'h> {
+    herd: &'h Herd,
+
+    /// The bucket that is allocated from, null until the first allocation.
+    /// It links to the buckets before it.
+    current: Cell<*mut SyncBucket>,
+}
+
+// The buckets of a member are not used by anything else.
+unsafe impl Send for Member<'_> {}
+
+impl<'h> Member<'h> {
+    /// Moves `value` into the herd, and returns a mutable reference to it.
+    pub fn alloc<T>(&self, value: T) -> &'h mut T {
+        self.try_alloc(value).expect("Allocation failed")
+    }
+
+    /// Like [`Member::alloc`], but returns an error
+    /// if the value could not be allocated.
+    pub fn try_alloc<T>(&self, value: T) -> Result<&'h mut T, AllocError> {
+        unsafe {
+            let ptr = self.malloc_bytes(mem::size_of::<T>(), mem::align_of::<T>())? as *mut T;
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Copies `src` into the herd.
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &'h mut [T] {
+        let size = mem::size_of_val(src);
+
+        unsafe {
+            let ptr = self
+                .malloc_bytes(size, mem::align_of::<T>())
+                .expect("Allocation failed") as *mut T;
+            ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len());
+            slice::from_raw_parts_mut(ptr, src.len())
+        }
+    }
+
+    /// Copies `s` into the herd.
+    pub fn alloc_str(&self, s: &str) -> &'h mut str {
+        let bytes = self.alloc_slice_copy(s.as_bytes());
+        unsafe { core::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Allocates uninitialized memory for `layout`.
+    pub fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.malloc_bytes(layout.size(), layout.align())?;
+        Ok(unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    fn malloc_bytes(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        if size == 0 {
+            return Ok(compat::dangling_mut(align));
+        }
+
+        loop {
+            let bucket = self.current.get();
+
+            if !bucket.is_null() {
+                if let Some(ptr) = unsafe { SyncBucket::malloc_unshared(bucket, size, align) } {
+                    return Ok(ptr);
+                }
+            }
+
+            self.grow(SyncBucket::room_for(size, align)?)?;
+        }
+    }
+
+    /// Starts a new bucket with room for at least `needed` bytes.
+    #[cold]
+    fn grow(&self, needed: usize) -> Result<(), AllocError> {
+        let full = self.current.get();
+        let capacity = if full.is_null() {
+            self.herd.capacity
+        } else {
+            unsafe { SyncBucket::capacity(full) }.saturating_mul(2)
+        };
+
+        let bucket = SyncBucket::new(capacity.max(needed), full)?;
+        self.current.set(bucket.as_ptr());
+        Ok(())
+    }
+}
+
+impl Drop for Member<'_> {
+    fn drop(&mut self) {
+        if let Some(bucket) = NonNull::new(self.current.get()) {
+            self.herd.give_back(bucket);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Herd;
+
+    use core::alloc::Layout;
+    use std::{sync::Barrier, thread, vec::Vec};
+
+    #[test]
+    fn test_parallel_alloc() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = if cfg!(miri) { 50 } else { 2000 };
+
+        let mut herd = Herd::with_capacity(64);
+        let barrier = Barrier::new(THREADS);
+
+        let results = thread::scope(|scope| {
+            let workers = (0..THREADS)
+                .map(|t| {
+                    let (herd, barrier) = (&herd, &barrier);
+
+                    scope.spawn(move || {
+                        let member = herd.get();
+                        barrier.wait();
+
+                        (0..PER_THREAD)
+                            .map(|n| &*member.alloc((t, n as u64)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for (t, values) in results.iter().enumerate() {
+            for (n, &&value) in values.iter().enumerate() {
+                assert_eq!(value, (t, n as u64));
+            }
+        }
+        drop(results);
+
+        assert_eq!(herd.allocated_bytes(), THREADS * PER_THREAD * 16);
+        assert!(herd.capacity() >= herd.allocated_bytes());
+    }
+
+    #[test]
+    fn test_reuse() {
+        let mut herd = Herd::with_capacity(64);
+
+        {
+            let member = herd.get();
+            assert_eq!(*member.alloc(1u32), 1);
+            assert_eq!(member.alloc_str("abc"), "abc");
+        }
+        assert_eq!(herd.allocated_bytes(), 7);
+        assert_eq!(herd.capacity(), 64);
+
+        {
+            let first = herd.get();
+            let second = herd.get();
+            first.alloc(2u32);
+            second.alloc(3u32);
+        }
+        assert_eq!(herd.allocated_bytes(), 16);
+        assert_eq!(herd.capacity(), 128);
+    }
+
+    #[test]
+    fn test_alloc_layout() {
+        let herd = Herd::with_capacity(16);
+        let member = herd.get();
+
+        for &align in &[1, 8, 64, 4096] {
+            let layout = Layout::from_size_align(10, align).unwrap();
+            let ptr = member.alloc_layout(layout).unwrap();
+            assert_eq!(crate::compat::addr(ptr.as_ptr()) % align, 0);
+        }
+
+        assert!(member.alloc_slice_copy::<u64>(&[]).is_empty());
+    }
+}
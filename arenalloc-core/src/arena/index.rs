@@ -0,0 +1,97 @@
+use core::convert::TryFrom;
+
+/// An integer type used as index by handle based structures.
+///
+/// Small index types keep handles, and the structures that store
+/// them, small. Converting an index that doesn't fit in the type
+/// fails instead of wrapping around. Structures generic over
+/// their index type use `u32` by default.
+pub trait ArenaIndex: Copy + Eq + Ord + core::hash::Hash + core::fmt::Debug {
+    /// The largest index that can be represented.
+    const MAX: usize;
+
+    /// Converts `index`, or returns `None` if it
+    /// doesn't fit in `Self`.
+    fn try_from_usize(index: usize) -> Option<Self>;
+
+    /// Converts `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` doesn't fit in `Self`.
+    fn from_usize(index: usize) -> Self {
+        match Self::try_from_usize(index) {
+            Some(index) => index,
+            None => panic!(
+                "index {} overflows the index type {}, whose maximum is {}",
+                index,
+                core::any::type_name::<Self>(),
+                Self::MAX
+            ),
+        }
+    }
+
+    /// Converts back to a `usize`.
+    fn index(self) -> usize;
+}
+
+macro_rules! impl_arena_index {
+    ($($t:ty),*) => {
+        $(
+            impl ArenaIndex for $t {
+                const MAX: usize = if (<$t>::MAX as u128) < (usize::MAX as u128) {
+                    <$t>::MAX as usize
+                } else {
+                    usize::MAX
+                };
+
+                fn try_from_usize(index: usize) -> Option<Self> {
+                    <$t>::try_from(index).ok()
+                }
+
+                fn index(self) -> usize {
+                    // Only indices that came from a `usize` are ever created.
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_arena_index!(u16, u32, u64, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaIndex;
+
+    use core::mem;
+
+    #[allow(dead_code)]
+    struct Edge<I> {
+        from: I,
+        to: I,
+    }
+
+    #[test]
+    fn test_boundary() {
+        assert_eq!(<u16 as ArenaIndex>::MAX, 65535);
+        assert_eq!(u16::try_from_usize(65535), Some(65535));
+        assert_eq!(u16::try_from_usize(65536), None);
+        assert_eq!(u16::from_usize(65535).index(), 65535);
+
+        assert_eq!(<usize as ArenaIndex>::MAX, usize::MAX);
+        assert_eq!(usize::from_usize(usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows the index type u16")]
+    fn test_overflow() {
+        u16::from_usize(65536);
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(mem::size_of::<Edge<u16>>(), 4);
+        assert_eq!(mem::size_of::<Edge<u32>>(), 8);
+        assert!(mem::size_of::<Edge<u16>>() < mem::size_of::<Edge<usize>>());
+    }
+}
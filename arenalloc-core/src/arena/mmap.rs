@@ -0,0 +1,133 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use super::{AllocError, Arena, BackingStore};
+
+/// Maps every bucket straight from the OS with `mmap`.
+///
+/// The mapping only reserves address space: a page is committed when
+/// it is first written to, so a bucket can be far larger than the memory
+/// that is used. The pages are zero to begin with, so zeroed buckets
+/// cost nothing extra. See [`Arena::with_virtual_capacity`].
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MmapStore;
+
+impl MmapStore {
+    fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    /// The length of the mapping for `layout`, a multiple of the page size.
+    fn mapping_len(layout: Layout) -> Option<usize> {
+        let page = Self::page_size();
+        Some(layout.size().checked_add(page - 1)? & !(page - 1))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const FLAGS: libc::c_int = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const FLAGS: libc::c_int = libc::MAP_PRIVATE | libc::MAP_ANON;
+
+unsafe impl BackingStore for MmapStore {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        // Mappings start at a page boundary.
+        if layout.align() > Self::page_size() {
+            return None;
+        }
+
+        let len = Self::mapping_len(layout)?;
+        let protection = libc::PROT_READ | libc::PROT_WRITE;
+
+        let ptr = unsafe { libc::mmap(core::ptr::null_mut(), len, protection, FLAGS, -1, 0) };
+
+        if ptr == libc::MAP_FAILED {
+            None
+        } else {
+            NonNull::new(ptr as *mut u8)
+        }
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Option<NonNull<u8>> {
+        // Anonymous mappings are zeroed by the OS.
+        self.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if let Some(len) = Self::mapping_len(layout) {
+            libc::munmap(ptr.as_ptr() as *mut libc::c_void, len);
+        }
+    }
+}
+
+impl Arena {
+    /// Creates an Arena with a single bucket of `bytes`, mapped with
+    /// [`MmapStore`]. Every allocation is in that bucket, one after the
+    /// other, and its pages only take memory once they are used. Once
+    /// it is full, allocating fails with [`AllocError::LimitExceeded`].
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::with_virtual_capacity(1 << 30).unwrap();
+    ///
+    /// arena.region(|s| {
+    ///     let a = s.alloc_slice_copy(&[1u8; 4096]).as_ptr();
+    ///     let b = s.alloc_slice_copy(&[2u8; 4096]).as_ptr();
+    ///     assert_eq!(b, a.wrapping_add(4096));
+    /// });
+    /// assert_eq!(arena.bucket_count(), 1);
+    /// ```
+    pub fn with_virtual_capacity(bytes: usize) -> Result<Self, AllocError> {
+        Arena::builder()
+            .initial_capacity(bytes)
+            .limit(bytes)
+            .backing_store(MmapStore)
+            .try_build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapStore;
+    use crate::{
+        arena::{AllocError, Arena, BackingStore},
+        compat,
+    };
+
+    use core::alloc::Layout;
+
+    #[test]
+    fn test_allocate() {
+        let layout = Layout::from_size_align(10_000, 8).unwrap();
+        let ptr = MmapStore.allocate_zeroed(layout).unwrap();
+
+        unsafe {
+            let bytes = core::slice::from_raw_parts_mut(ptr.as_ptr(), layout.size());
+            assert!(bytes.iter().all(|&b| b == 0));
+            bytes.fill(1);
+
+            MmapStore.deallocate(ptr, layout);
+        }
+
+        let huge = Layout::from_size_align(64, 1 << 30).unwrap();
+        assert!(MmapStore.allocate(huge).is_none());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_virtual_capacity() {
+        let arena = Arena::with_virtual_capacity(1 << 36).unwrap();
+        assert_eq!(arena.capacity(), 1 << 36);
+
+        arena.region(|s| {
+            let first = s.alloc(0u64) as *mut u64;
+            let big = s.malloc::<u8>(1 << 20).unwrap();
+            assert_eq!(compat::addr(big), compat::addr(first) + 8);
+
+            assert!(matches!(
+                s.malloc::<u8>(1 << 36),
+                Err(AllocError::LimitExceeded { .. })
+            ));
+        });
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(any(feature = "nightly", feature = "allocator-api2"))]
+mod allocator;
+#[allow(clippy::module_inception)]
+mod arena;
+mod arenabox;
+mod arenaref;
+#[cfg(feature = "bench-support")]
+mod bench;
+mod bucket;
+mod builder;
+mod drops;
+mod error;
+mod frozen;
+#[cfg(target_has_atomic = "ptr")]
+mod global;
+mod handler;
+#[cfg(target_has_atomic = "ptr")]
+mod herd;
+mod index;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+mod objectpool;
+mod observer;
+#[cfg(feature = "debug-poison")]
+pub mod poison;
+#[cfg(target_has_atomic = "ptr")]
+mod pool;
+pub mod ptrtable;
+mod sanitize;
+mod send;
+mod slice;
+mod store;
+mod stream;
+mod string;
+#[cfg(target_has_atomic = "ptr")]
+mod sync;
+#[cfg(feature = "debug-track")]
+pub mod track;
+mod typed;
+#[cfg(feature = "type-stats")]
+pub mod typestats;
+#[cfg(feature = "bench-support")]
+pub mod workloads;
+pub use arena::*;
+pub use arenabox::ArenaBox;
+pub use arenaref::*;
+#[cfg(feature = "bench-support")]
+pub use bench::Counters;
+pub use builder::{ArenaBuilder, Growth};
+pub use error::*;
+pub use frozen::FrozenArena;
+#[cfg(target_has_atomic = "ptr")]
+pub use global::ArenaGlobal;
+pub use handler::HandlerId;
+#[cfg(target_has_atomic = "ptr")]
+pub use herd::{Herd, Member};
+pub use index::ArenaIndex;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::MmapStore;
+pub use objectpool::{Pool, PoolBox};
+pub use observer::*;
+#[cfg(target_has_atomic = "ptr")]
+pub use pool::{ArenaPool, PooledArena};
+pub use send::{DetachedBox, SendArena};
+pub use store::{BackingStore, GlobalStore};
+pub use stream::StreamStats;
+#[cfg(target_has_atomic = "ptr")]
+pub use sync::SyncArena;
+pub use typed::TypedArena;
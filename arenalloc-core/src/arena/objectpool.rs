@@ -0,0 +1,268 @@
+use core::{
+    cell::Cell,
+    fmt,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+};
+
+use super::{AllocError, Arena};
+
+/// A slot of a [`Pool`], holding a value while it
+/// is in use, and the next free slot while it is not.
+union Slot<T> {
+    value: ManuallyDrop<T>,
+    next: *mut Slot<T>,
+}
+
+/// Hands out slots for `T`'s that are given back when the
+/// [`PoolBox`] is dropped, and reused by later allocations.
+/// For objects that come and go all the time, unlike
+/// an [`Arena`] that only gives memory back when it is reset.
+///
+/// The slots are bump allocated from an arena of its
+/// own, and only freed when the Pool is dropped.
+/// ```
+/// use arenalloc_core::arena::Pool;
+///
+/// let pool = Pool::new();
+///
+/// let a = pool.alloc(String::from("a"));
+/// let b = pool.alloc(String::from("b"));
+/// drop(a);
+///
+/// // Reuses the slot of `a`.
+/// let c = pool.alloc(String::from("c"));
+/// assert_eq!((b.as_str(), c.as_str()), ("b", "c"));
+/// assert_eq!((pool.len(), pool.capacity()), (2, 2));
+/// ```
+pub struct Pool<T> {
+    arena: Arena,
+
+    /// The first free slot, or null.
+    free: Cell<*mut Slot<T>>,
+
+    /// The number of values in use.
+    len: Cell<usize>,
+
+    /// The number of slots allocated.
+    slots: Cell<usize>,
+
+    marker: PhantomData<T>,
+}
+
+impl<T> Pool<T> {
+    pub fn new() -> Self {
+        Self::from_arena(Arena::new())
+    }
+
+    /// Creates a Pool with room for `capacity` values before it grows.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let bytes = mem::size_of::<Slot<T>>().saturating_mul(capacity);
+        Self::from_arena(Arena::with_capacity(bytes))
+    }
+
+    fn from_arena(arena: Arena) -> Self {
+        Self {
+            arena,
+            free: Cell::new(ptr::null_mut()),
+            len: Cell::new(0),
+            slots: Cell::new(0),
+            marker: PhantomData,
+        }
+    }
+
+    /// Moves `value` into a free slot, or into a new one
+    /// if no slot is free.
+    pub fn alloc(&self, value: T) -> PoolBox<'_, T> {
+        self.try_alloc(value).expect("Allocation failed")
+    }
+
+    /// Like [`Pool::alloc`], but returns an error
+    /// if the value could not be allocated.
+    pub fn try_alloc(&self, value: T) -> Result<PoolBox<'_, T>, AllocError> {
+        let slot = match self.free.get() {
+            free if !free.is_null() => {
+                self.free.set(unsafe { (*free).next });
+                free
+            }
+            _ => {
+                let slot = self.arena.malloc::<Slot<T>>(1)?;
+                self.slots.set(self.slots.get() + 1);
+                slot
+            }
+        };
+
+        unsafe {
+            slot.write(Slot {
+                value: ManuallyDrop::new(value),
+            });
+            self.len.set(self.len.get() + 1);
+
+            Ok(PoolBox {
+                pool: self,
+                slot: NonNull::new_unchecked(slot),
+            })
+        }
+    }
+
+    /// The number of values in use.
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of slots, in use or free.
+    pub fn capacity(&self) -> usize {
+        self.slots.get()
+    }
+
+    /// Puts `slot`, whose value was moved out or dropped, on the free list.
+    unsafe fn release(&self, slot: NonNull<Slot<T>>) {
+        slot.as_ptr().write(Slot {
+            next: self.free.get(),
+        });
+        self.free.set(slot.as_ptr());
+        self.len.set(self.len.get() - 1);
+    }
+}
+
+impl<T> Default for Pool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+/// An owning pointer to a value in a [`Pool`]. Dropping
+/// it drops the value, and gives the slot back to the Pool.
+pub struct PoolBox<'pool, T> {
+    pool: &'pool Pool<T>,
+    slot: NonNull<Slot<T>>,
+}
+
+impl<'pool, T> PoolBox<'pool, T> {
+    /// Moves the value out, and gives the slot back.
+    pub fn into_inner(this: Self) -> T {
+        let this = ManuallyDrop::new(this);
+
+        unsafe {
+            let value = ptr::read(&*this.slot.as_ref().value);
+            this.pool.release(this.slot);
+            value
+        }
+    }
+
+    /// Keeps the value and its slot for as long as the Pool
+    /// is borrowed. The value is never dropped.
+    pub fn leak(this: Self) -> &'pool mut T {
+        let mut this = ManuallyDrop::new(this);
+        unsafe { &mut this.slot.as_mut().value }
+    }
+}
+
+impl<T> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.slot.as_mut().value);
+            self.pool.release(self.slot);
+        }
+    }
+}
+
+impl<T> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &self.slot.as_ref().value }
+    }
+}
+
+impl<T> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut self.slot.as_mut().value }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolBox<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pool, PoolBox};
+    use crate::testutil::DropCount;
+
+    use alloc::{string::String, vec::Vec};
+    use core::cell::Cell;
+
+    #[test]
+    fn test_reuses_slots() {
+        let pool = Pool::with_capacity(4);
+        let mut live = Vec::new();
+
+        for n in 0..100u64 {
+            live.push(pool.alloc(n));
+
+            // Objects leave in a different order than they came.
+            if live.len() > 3 {
+                live.swap_remove(n as usize % 3);
+            }
+        }
+
+        assert_eq!((pool.len(), pool.capacity()), (3, 4));
+        assert!(live.iter().any(|b| **b == 99));
+    }
+
+    #[test]
+    fn test_drops() {
+        let drops = Cell::new(0);
+        let pool = Pool::new();
+
+        let a = pool.alloc(DropCount(&drops));
+        let b = pool.alloc(DropCount(&drops));
+        drop(a);
+        assert_eq!(drops.get(), 1);
+
+        let moved = PoolBox::into_inner(b);
+        assert_eq!((drops.get(), pool.len()), (1, 0));
+        drop(moved);
+        assert_eq!(drops.get(), 2);
+
+        PoolBox::leak(pool.alloc(DropCount(&drops)));
+        pool.alloc(DropCount(&drops));
+        assert_eq!((pool.len(), pool.capacity()), (1, 2));
+        drop(pool);
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn test_mutate() {
+        let pool = Pool::default();
+
+        let mut name = pool.alloc(String::from("ferris"));
+        name.push_str(" the crab");
+        assert_eq!(alloc::format!("{:?}", name), "\"ferris the crab\"");
+        assert_eq!(PoolBox::into_inner(name), "ferris the crab");
+
+        let units = Pool::new();
+        for _ in 0..10 {
+            units.alloc(());
+        }
+        assert_eq!(units.capacity(), 1);
+    }
+}
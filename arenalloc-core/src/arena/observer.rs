@@ -0,0 +1,131 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+/// Gets notified of what an Arena does, once it is
+/// installed with [`Arena::set_observer`](super::Arena::set_observer).
+/// Every method does nothing by default.
+/// ```
+/// use arenalloc_core::arena::{AllocObserver, Arena};
+/// use std::{alloc::Layout, cell::Cell, ptr::NonNull, rc::Rc};
+///
+/// struct Allocations(Rc<Cell<usize>>);
+///
+/// impl AllocObserver for Allocations {
+///     fn on_alloc(&self, _: Layout, _: NonNull<u8>) {
+///         self.0.set(self.0.get() + 1);
+///     }
+/// }
+///
+/// let count = Rc::new(Cell::new(0));
+/// let mut arena = Arena::new();
+/// arena.set_observer(Some(Box::new(Allocations(count.clone()))));
+///
+/// arena.region(|s| {
+///     s.alloc(1u32);
+///     s.alloc_slice_copy(&[1, 2, 3]);
+/// });
+/// assert_eq!(count.get(), 2);
+/// ```
+pub trait AllocObserver {
+    /// Called after `layout` was allocated at `ptr`.
+    fn on_alloc(&self, layout: Layout, ptr: NonNull<u8>) {
+        let _ = (layout, ptr);
+    }
+
+    /// Called after a new bucket of `size` bytes was allocated.
+    fn on_grow(&self, size: usize) {
+        let _ = size;
+    }
+
+    /// Called after [`Arena::reset`](super::Arena::reset).
+    fn on_reset(&self) {}
+}
+
+/// Emits a `tracing` event at the trace level for everything it observes.
+/// ```
+/// use arenalloc_core::arena::{Arena, TracingObserver};
+///
+/// let mut arena = Arena::new();
+/// arena.set_observer(Some(Box::new(TracingObserver)));
+/// ```
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TracingObserver;
+
+#[cfg(feature = "tracing")]
+impl AllocObserver for TracingObserver {
+    fn on_alloc(&self, layout: Layout, ptr: NonNull<u8>) {
+        tracing::trace!(
+            size = layout.size(),
+            align = layout.align(),
+            ptr = ?ptr,
+            "arena allocation"
+        );
+    }
+
+    fn on_grow(&self, size: usize) {
+        tracing::trace!(size, "arena bucket allocated");
+    }
+
+    fn on_reset(&self) {
+        tracing::trace!("arena reset");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AllocObserver;
+    use crate::arena::Arena;
+
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::{alloc::Layout, cell::RefCell, ptr::NonNull};
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Alloc(usize, usize),
+        Grow(usize),
+        Reset,
+    }
+
+    struct Recorder(Rc<RefCell<Vec<Event>>>);
+
+    impl AllocObserver for Recorder {
+        fn on_alloc(&self, layout: Layout, _: NonNull<u8>) {
+            let event = Event::Alloc(layout.size(), layout.align());
+            self.0.borrow_mut().push(event);
+        }
+
+        fn on_grow(&self, size: usize) {
+            self.0.borrow_mut().push(Event::Grow(size));
+        }
+
+        fn on_reset(&self) {
+            self.0.borrow_mut().push(Event::Reset);
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut arena = Arena::new();
+        arena.set_observer(Some(Box::new(Recorder(events.clone()))));
+
+        arena.region(|s| {
+            s.alloc([0u64; 60]);
+            s.alloc([0u16; 100]);
+            s.alloc_back(());
+        });
+        arena.reset();
+
+        assert_eq!(
+            *events.borrow(),
+            [
+                Event::Grow(512),
+                Event::Alloc(480, 8),
+                Event::Grow(1024),
+                Event::Alloc(200, 2),
+                Event::Alloc(0, 1),
+                Event::Reset,
+            ]
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Debugging aids behind the `debug-poison` feature.
+//!
+//! Memory is filled with [`FREE_POISON`] whenever it is given back:
+//! when a region ends, and when the Arena is rewound or reset. Reading
+//! it afterwards shows `0xDE` bytes instead of the old values.
+
+use core::fmt;
+
+use alloc::collections::VecDeque;
+
+/// Written over freshly allocated memory.
+pub const ALLOC_POISON: u8 = 0xA5;
+
+/// Written over memory that is given back to the arena.
+pub const FREE_POISON: u8 = 0xDE;
+
+/// The number of allocations the log remembers.
+/// Older allocations are forgotten first.
+pub const LOG_CAPACITY: usize = 1024;
+
+/// An allocation made by the Arena.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AllocationRecord {
+    /// The name of the allocated type.
+    pub type_name: &'static str,
+
+    /// The size of the allocation in bytes.
+    pub size: usize,
+
+    /// The index of the bucket the allocation is in.
+    pub bucket: usize,
+
+    /// The offset of the allocation in its bucket.
+    pub offset: usize,
+}
+
+impl fmt::Display for AllocationRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bucket {} offset {}: {} bytes of {}",
+            self.bucket, self.offset, self.size, self.type_name
+        )
+    }
+}
+
+/// A ring buffer of the last `LOG_CAPACITY` allocations. It is
+/// created by the first allocation, as `VecDeque::new`
+/// is not `const` on the MSRV.
+pub(super) struct AllocationLog {
+    records: Option<VecDeque<AllocationRecord>>,
+}
+
+impl AllocationLog {
+    pub(super) const fn new() -> Self {
+        Self { records: None }
+    }
+
+    pub(super) fn push(&mut self, record: AllocationRecord) {
+        let records = self.records.get_or_insert_with(VecDeque::new);
+
+        if records.len() == LOG_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    pub(super) fn records(&self) -> impl Iterator<Item = &AllocationRecord> {
+        self.records.iter().flatten()
+    }
+}
@@ -0,0 +1,241 @@
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::atomic::AtomicBool,
+};
+
+use alloc::vec::Vec;
+
+use super::{sync::SpinLock, Arena};
+
+/// Keeps arenas that are not in use, so their buckets
+/// can be used again instead of being freed, for example
+/// to give every request of a server an arena.
+///
+/// [`ArenaPool::get`] hands out an arena, which goes back into
+/// the pool when the [`PooledArena`] is dropped. The pool can
+/// be owned, or be a `static` shared by all threads. Arenas with
+/// a [`BackingStore`](super::BackingStore) of their own are freed
+/// instead of going back into the pool.
+/// ```
+/// use arenalloc_core::arena::ArenaPool;
+///
+/// static POOL: ArenaPool = ArenaPool::new();
+///
+/// fn handle(request: &str) -> usize {
+///     let arena = POOL.get();
+///
+///     arena.region(|s| s.alloc_str(request).split(' ').count())
+/// }
+///
+/// assert_eq!(handle("GET /index.html"), 2);
+/// assert_eq!(handle("GET /favicon.ico"), 2);
+/// assert_eq!(POOL.idle(), 1);
+/// ```
+pub struct ArenaPool {
+    /// The arenas that are not in use, which are reset
+    /// and have no observer.
+    idle: UnsafeCell<Vec<Arena>>,
+
+    /// Held while `idle` is used.
+    lock: AtomicBool,
+}
+
+// `idle` is only used while holding `lock`. A reset arena without
+// an observer holds nothing but its buckets, so it can be used
+// by another thread.
+unsafe impl Send for ArenaPool {}
+unsafe impl Sync for ArenaPool {}
+
+impl ArenaPool {
+    pub const fn new() -> Self {
+        Self {
+            idle: UnsafeCell::new(Vec::new()),
+            lock: AtomicBool::new(false),
+        }
+    }
+
+    /// Takes an arena out of the pool,
+    /// or creates one if the pool is empty.
+    pub fn get(&self) -> PooledArena<'_> {
+        self.get_or_else(Arena::new)
+    }
+
+    /// Like [`ArenaPool::get`], but creates the arena
+    /// with `f` if the pool is empty.
+    pub fn get_or_else(&self, f: impl FnOnce() -> Arena) -> PooledArena<'_> {
+        let arena = {
+            let _lock = SpinLock::acquire(&self.lock);
+            unsafe { (*self.idle.get()).pop() }
+        };
+
+        PooledArena {
+            pool: self,
+            arena: ManuallyDrop::new(arena.unwrap_or_else(f)),
+        }
+    }
+
+    /// The number of arenas in the pool that are not in use.
+    pub fn idle(&self) -> usize {
+        let _lock = SpinLock::acquire(&self.lock);
+        unsafe { (*self.idle.get()).len() }
+    }
+
+    /// Frees the arenas that are not in use.
+    pub fn clear(&self) {
+        let idle = {
+            let _lock = SpinLock::acquire(&self.lock);
+            unsafe { core::mem::take(&mut *self.idle.get()) }
+        };
+
+        drop(idle);
+    }
+
+    fn give_back(&self, mut arena: Arena) {
+        // The store may not be usable by other threads.
+        if !arena.uses_global_store() {
+            return;
+        }
+        arena.reset();
+        arena.set_observer(None);
+
+        let _lock = SpinLock::acquire(&self.lock);
+        unsafe { (*self.idle.get()).push(arena) }
+    }
+}
+
+impl Default for ArenaPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ArenaPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaPool")
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+/// An arena taken out of an [`ArenaPool`], which is
+/// reset and put back into the pool when it is dropped.
+pub struct PooledArena<'pool> {
+    pool: &'pool ArenaPool,
+    arena: ManuallyDrop<Arena>,
+}
+
+impl Deref for PooledArena<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl DerefMut for PooledArena<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.arena
+    }
+}
+
+impl Drop for PooledArena<'_> {
+    fn drop(&mut self) {
+        let arena = unsafe { ManuallyDrop::take(&mut self.arena) };
+        self.pool.give_back(arena);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaPool;
+    use crate::arena::{AllocObserver, Arena, GlobalStore};
+
+    use alloc::{boxed::Box, rc::Rc};
+    use core::{alloc::Layout, cell::Cell, ptr::NonNull};
+    use std::{thread, vec::Vec};
+
+    #[test]
+    fn test_reuse() {
+        let pool = ArenaPool::new();
+
+        {
+            let arena = pool.get();
+            arena.region(|s| {
+                s.alloc([0u8; 100]);
+                s.alloc([0u8; 4000]);
+            });
+            assert_eq!(arena.bucket_count(), 2);
+        }
+        assert_eq!(pool.idle(), 1);
+
+        let arena = pool.get();
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(arena.bucket_count(), 2);
+        assert_eq!(arena.allocated_bytes(), 0);
+
+        let other = pool.get_or_else(|| Arena::with_capacity(64));
+        assert_eq!(other.capacity(), 64);
+
+        drop((arena, other));
+        assert_eq!(pool.idle(), 2);
+
+        pool.clear();
+        assert_eq!(pool.idle(), 0);
+
+        drop(pool.get_or_else(|| Arena::builder().backing_store(GlobalStore).build()));
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[test]
+    fn test_observer_removed() {
+        struct Allocations(Rc<Cell<usize>>);
+
+        impl AllocObserver for Allocations {
+            fn on_alloc(&self, _: Layout, _: NonNull<u8>) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let pool = ArenaPool::new();
+        let count = Rc::new(Cell::new(0));
+
+        for _ in 0..2 {
+            let mut arena = pool.get();
+            arena.region(|s| {
+                s.alloc(1u8);
+            });
+            arena.set_observer(Some(Box::new(Allocations(count.clone()))));
+            arena.region(|s| {
+                s.alloc(1u8);
+            });
+        }
+        assert_eq!(count.get(), 2);
+        assert_eq!(Rc::strong_count(&count), 1);
+    }
+
+    #[test]
+    fn test_threads() {
+        static POOL: ArenaPool = ArenaPool::new();
+
+        let handles = (0..4u32)
+            .map(|n| {
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        let arena = POOL.get();
+                        let sum =
+                            arena.region(|s| s.alloc_slice_copy(&[n; 100]).iter().sum::<u32>());
+                        assert_eq!(sum, n * 100);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!((1..=4).contains(&POOL.idle()));
+    }
+}
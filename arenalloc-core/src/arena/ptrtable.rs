@@ -0,0 +1,229 @@
+use crate::arena::{ArenaRef, Scope};
+
+use core::{
+    cell::Cell,
+    marker::PhantomData,
+    ptr::{self, NonNull},
+};
+
+/// A fixed-length table of optional [`ArenaRef`]s,
+/// created by [`Scope::alloc_ptr_table`].
+///
+/// Every entry is stored as a single nullable pointer,
+/// so the table takes `len * size_of::<*const T>()` bytes
+/// of the arena. The number of set entries is
+/// kept up to date on every `set` and `clear`.
+/// ```
+/// use arenalloc_core::arena::Arena;
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let table = s.alloc_ptr_table(4);
+///     let value = s.alloc_ref(10);
+///
+///     table.set(2, value);
+///
+///     assert_eq!(table.get(2).map(|r| *r), Some(10));
+///     assert!(table.get(1).is_none());
+///     assert_eq!(table.count(), 1);
+/// });
+/// ```
+///
+/// Only references of the same region are accepted:
+/// ```compile_fail
+/// use arenalloc_core::arena::Arena;
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|outer| {
+///     let table = outer.alloc_ptr_table(1);
+///
+///     arena.region(|inner| {
+///         table.set(0, inner.alloc_ref(10));
+///     });
+/// });
+/// ```
+pub struct LocalPtrTable<'a, 'scope, T> {
+    entries: NonNull<Cell<*const T>>,
+    len: usize,
+    count: Cell<usize>,
+    scope: PhantomData<&'a Scope<'scope>>,
+    marker: PhantomData<ArenaRef<'scope, T>>,
+}
+
+impl<'scope> Scope<'scope> {
+    /// Allocates a [`LocalPtrTable`] of `len` empty entries.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_ptr_table<'a, T>(&'a self, len: usize) -> LocalPtrTable<'a, 'scope, T> {
+        let entries = self
+            .malloc::<Cell<*const T>>(len)
+            .expect("Allocation failed");
+
+        unsafe {
+            for i in 0..len {
+                entries.add(i).write(Cell::new(ptr::null()));
+            }
+
+            LocalPtrTable {
+                entries: NonNull::new_unchecked(entries),
+                len,
+                count: Cell::new(0),
+                scope: PhantomData,
+                marker: PhantomData,
+            }
+        }
+    }
+}
+
+impl<'a, 'scope, T> LocalPtrTable<'a, 'scope, T> {
+    fn entries(&self) -> &[Cell<*const T>] {
+        unsafe { core::slice::from_raw_parts(self.entries.as_ptr(), self.len) }
+    }
+
+    fn entry(&self, index: usize) -> &Cell<*const T> {
+        match self.entries().get(index) {
+            Some(entry) => entry,
+            None => panic!(
+                "index out of bounds: the len is {} but the index is {}",
+                self.len, index
+            ),
+        }
+    }
+
+    /// The number of entries, set or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of set entries.
+    pub fn count(&self) -> usize {
+        self.count.get()
+    }
+
+    /// Sets the entry at `index`, returning the previous value.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, value: ArenaRef<'scope, T>) -> Option<ArenaRef<'scope, T>> {
+        let old = self.entry(index).replace(ArenaRef::as_ptr(value));
+
+        if old.is_null() {
+            self.count.set(self.count.get() + 1);
+        }
+        unsafe { Self::to_ref(old) }
+    }
+
+    /// Returns the entry at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<ArenaRef<'scope, T>> {
+        unsafe { Self::to_ref(self.entry(index).get()) }
+    }
+
+    /// Clears the entry at `index`, returning the previous value.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn clear(&self, index: usize) -> Option<ArenaRef<'scope, T>> {
+        let old = self.entry(index).replace(ptr::null());
+
+        if !old.is_null() {
+            self.count.set(self.count.get() - 1);
+        }
+        unsafe { Self::to_ref(old) }
+    }
+
+    /// Returns an iterator over the set entries and their index.
+    pub fn iter(&self) -> Iter<'_, 'scope, T> {
+        Iter {
+            entries: self.entries().iter().enumerate(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Only pointers taken from an `ArenaRef<'scope, T>`
+    /// are ever stored in the table.
+    unsafe fn to_ref(ptr: *const T) -> Option<ArenaRef<'scope, T>> {
+        NonNull::new(ptr as *mut T).map(|ptr| ArenaRef::from_raw(ptr))
+    }
+}
+
+/// The iterator returned by [`LocalPtrTable::iter`].
+pub struct Iter<'t, 'scope, T> {
+    entries: core::iter::Enumerate<core::slice::Iter<'t, Cell<*const T>>>,
+    marker: PhantomData<ArenaRef<'scope, T>>,
+}
+
+impl<'scope, T> Iterator for Iter<'_, 'scope, T> {
+    type Item = (usize, ArenaRef<'scope, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.by_ref().find_map(|(index, entry)| {
+            let entry = unsafe { LocalPtrTable::<T>::to_ref(entry.get())? };
+            Some((index, entry))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{Arena, ArenaRef};
+
+    use alloc::vec::Vec;
+    use core::mem;
+
+    #[test]
+    fn test_sparse() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let table = s.alloc_ptr_table(100);
+
+            for n in (0..100).step_by(10) {
+                assert!(table.set(n, s.alloc_ref(n)).is_none());
+            }
+            assert_eq!(table.count(), 10);
+
+            let set = table.iter().map(|(i, r)| (i, *r)).collect::<Vec<_>>();
+            assert_eq!(
+                set,
+                (0..100).step_by(10).map(|n| (n, n)).collect::<Vec<_>>()
+            );
+
+            assert_eq!(table.clear(10).map(|r| *r), Some(10));
+            assert!(table.clear(10).is_none());
+            assert!(table.clear(11).is_none());
+            assert_eq!(table.count(), 9);
+
+            let shared = s.alloc_ref(1000);
+            assert_eq!(table.set(20, shared).map(|r| *r), Some(20));
+            assert_eq!(table.count(), 9);
+            assert!(ArenaRef::ptr_eq(table.get(20).unwrap(), shared));
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let table = s.alloc_ptr_table(2);
+            table.set(2, s.alloc_ref(0u8));
+        });
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(
+            mem::size_of::<Option<ArenaRef<u64>>>(),
+            mem::size_of::<*const u64>()
+        );
+    }
+}
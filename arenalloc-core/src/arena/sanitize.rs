@@ -0,0 +1,121 @@
+//! Tells AddressSanitizer and Valgrind which bytes of a bucket are
+//! handed out, with the `sanitizers` feature. Without it, or when
+//! running under neither, marking bytes does nothing.
+//!
+//! The bytes between the front and the back of a bucket are marked
+//! unaddressable, so reading or writing them is reported.
+//! AddressSanitizer is used when the crate is compiled with
+//! `-Zsanitizer=address`, Valgrind on x86_64 Linux.
+
+/// Marks the `len` bytes at `ptr` as unaddressable.
+#[inline]
+pub(super) unsafe fn poison(ptr: *mut u8, len: usize) {
+    #[cfg(all(feature = "sanitizers", arenalloc_asan))]
+    asan::__asan_poison_memory_region(ptr as *const _, len);
+
+    #[cfg(all(
+        feature = "sanitizers",
+        target_arch = "x86_64",
+        target_os = "linux",
+        not(miri)
+    ))]
+    valgrind::request(valgrind::MAKE_MEM_NOACCESS, ptr, len);
+
+    let _ = (ptr, len);
+}
+
+/// Marks the `len` bytes at `ptr` as addressable,
+/// but not yet initialized.
+#[inline]
+pub(super) unsafe fn unpoison(ptr: *mut u8, len: usize) {
+    #[cfg(all(feature = "sanitizers", arenalloc_asan))]
+    asan::__asan_unpoison_memory_region(ptr as *const _, len);
+
+    #[cfg(all(
+        feature = "sanitizers",
+        target_arch = "x86_64",
+        target_os = "linux",
+        not(miri)
+    ))]
+    valgrind::request(valgrind::MAKE_MEM_UNDEFINED, ptr, len);
+
+    let _ = (ptr, len);
+}
+
+#[cfg(all(feature = "sanitizers", arenalloc_asan))]
+mod asan {
+    extern "C" {
+        pub(super) fn __asan_poison_memory_region(addr: *const core::ffi::c_void, size: usize);
+        pub(super) fn __asan_unpoison_memory_region(addr: *const core::ffi::c_void, size: usize);
+    }
+}
+
+#[cfg(all(
+    feature = "sanitizers",
+    target_arch = "x86_64",
+    target_os = "linux",
+    not(miri)
+))]
+mod valgrind {
+    use core::arch::asm;
+
+    /// `VG_USERREQ_TOOL_BASE('M', 'C')`, the requests of Memcheck.
+    const MEMCHECK: usize = (b'M' as usize) << 24 | (b'C' as usize) << 16;
+
+    pub(super) const MAKE_MEM_NOACCESS: usize = MEMCHECK;
+    pub(super) const MAKE_MEM_UNDEFINED: usize = MEMCHECK + 1;
+
+    /// Sends a client request about the `len` bytes at `ptr`.
+    /// When not running under Valgrind, this does nothing.
+    #[inline]
+    pub(super) unsafe fn request(request: usize, ptr: *mut u8, len: usize) {
+        let args = [request, crate::compat::addr(ptr), len, 0, 0, 0];
+
+        // The sequence from `valgrind.h` for amd64, which
+        // rotates `rdi` by 128 bits and exchanges `rbx` with itself.
+        asm!(
+            "rol rdi, 3",
+            "rol rdi, 13",
+            "rol rdi, 61",
+            "rol rdi, 51",
+            "xchg rbx, rbx",
+            inout("rdx") 0usize => _,
+            in("rax") args.as_ptr(),
+            out("rdi") _,
+            options(nostack),
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "sanitizers", arenalloc_asan))]
+mod tests {
+    use crate::arena::Arena;
+
+    extern "C" {
+        fn __asan_address_is_poisoned(addr: *const core::ffi::c_void) -> i32;
+    }
+
+    fn is_poisoned(ptr: *const u8) -> bool {
+        unsafe { __asan_address_is_poisoned(ptr as *const _) != 0 }
+    }
+
+    #[test]
+    fn test_free_bytes_are_poisoned() {
+        let mut arena = Arena::with_capacity(256);
+
+        let (front, back) = arena.region(|s| {
+            let front = s.malloc::<u64>(1).unwrap() as *const u8;
+            let back = s.alloc_back(0u64) as *const u64 as *const u8;
+
+            assert!(!is_poisoned(front) && !is_poisoned(back));
+            assert!(is_poisoned(front.wrapping_add(64)));
+            (front, back)
+        });
+        assert!(is_poisoned(front));
+        assert!(!is_poisoned(back));
+
+        arena.reset();
+        assert!(is_poisoned(back));
+    }
+}
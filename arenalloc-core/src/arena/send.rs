@@ -0,0 +1,253 @@
+use core::{fmt, ptr::NonNull};
+
+use super::{Arena, ArenaBox};
+
+/// An [`Arena`] that can be moved to another thread, made by
+/// [`Arena::into_send`]. It can't allocate until it is turned
+/// back into an Arena by [`SendArena::into_inner`].
+///
+/// Arena itself is not `Send`, as it may own an observer, a
+/// [`BackingStore`](super::BackingStore) or values waiting to be
+/// dropped that are not. `into_send` gets rid of those first.
+///
+/// Values built on one thread are found again on the
+/// other through a [`DetachedBox`].
+/// ```
+/// use arenalloc_core::arena::{Arena, ArenaBox};
+/// use std::thread;
+///
+/// let arena = Arena::new().into_send();
+///
+/// let (arena, names) = thread::spawn(move || {
+///     let arena = arena.into_inner();
+///     let names = arena.alloc(["ada", "grace"].map(String::from));
+///     let names = ArenaBox::detach(names);
+///     (arena.into_send(), names)
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let arena = arena.into_inner();
+/// let names = unsafe { names.attach(&arena) };
+/// assert_eq!(names[1], "grace");
+/// ```
+pub struct SendArena {
+    arena: Arena,
+}
+
+// `Arena::into_send` makes sure that nothing in the arena
+// but the buckets and plain bookkeeping is left, and
+// nothing can be allocated before `into_inner`.
+unsafe impl Send for SendArena {}
+
+impl Arena {
+    /// Makes the arena movable to another thread, see [`SendArena`].
+    /// The values waiting to be dropped, by
+    /// [`Scope::alloc_with_drop`](super::Scope::alloc_with_drop)
+    /// inside a region that was kept, are dropped first. The
+    /// observer is removed.
+    ///
+    /// # Panics
+    /// Panics if the Arena has a [`BackingStore`](super::BackingStore)
+    /// of its own, which may not be usable by other threads.
+    pub fn into_send(mut self) -> SendArena {
+        assert!(
+            self.uses_global_store(),
+            "an Arena with a BackingStore of its own can't be sent"
+        );
+
+        // No region is open, so no Scope can use them anymore.
+        unsafe { self.drops().drop_from(0) };
+        self.set_observer(None);
+
+        SendArena { arena: self }
+    }
+
+    /// Moves the arena into a new thread of `threads`, which
+    /// calls `f` with it. Joining the thread hands back the
+    /// arena with the result of `f`, see [`SendArena`].
+    ///
+    /// ```
+    /// use arenalloc_core::arena::{Arena, ArenaBox};
+    /// use std::thread;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// let (arena, squares) = thread::scope(|threads| {
+    ///     arena
+    ///         .spawn_scoped(threads, |arena| {
+    ///             let mut squares = arena.alloc([0u64; 100]);
+    ///             for (n, square) in squares.iter_mut().enumerate() {
+    ///                 *square = (n * n) as u64;
+    ///             }
+    ///             ArenaBox::detach(squares)
+    ///         })
+    ///         .join()
+    ///         .unwrap()
+    /// });
+    ///
+    /// let arena = arena.into_inner();
+    /// let squares = unsafe { squares.attach(&arena) };
+    /// assert_eq!(squares[9], 81);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the Arena has a [`BackingStore`](super::BackingStore)
+    /// of its own, like [`Arena::into_send`].
+    #[cfg(feature = "std")]
+    pub fn spawn_scoped<'scope, 'env, F, T>(
+        self,
+        threads: &'scope std::thread::Scope<'scope, 'env>,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, (SendArena, T)>
+    where
+        F: FnOnce(&Arena) -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.into_send().spawn_scoped(threads, f)
+    }
+}
+
+impl SendArena {
+    /// Allows allocating again, on the current thread.
+    /// Values stay where they are.
+    pub fn into_inner(self) -> Arena {
+        self.arena
+    }
+
+    /// See [`Arena::spawn_scoped`].
+    #[cfg(feature = "std")]
+    pub fn spawn_scoped<'scope, 'env, F, T>(
+        self,
+        threads: &'scope std::thread::Scope<'scope, 'env>,
+        f: F,
+    ) -> std::thread::ScopedJoinHandle<'scope, (SendArena, T)>
+    where
+        F: FnOnce(&Arena) -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        threads.spawn(move || {
+            let arena = self.into_inner();
+            let out = f(&arena);
+            (arena.into_send(), out)
+        })
+    }
+
+    /// See [`Arena::allocated_bytes`].
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+
+    /// See [`Arena::capacity`].
+    pub fn capacity(&self) -> usize {
+        self.arena.capacity()
+    }
+}
+
+impl fmt::Debug for SendArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendArena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+/// An [`ArenaBox`] without the borrow of its Arena, made by
+/// [`ArenaBox::detach`], so it can move between threads along
+/// with a [`SendArena`]. If it is dropped, the value is not.
+pub struct DetachedBox<T> {
+    ptr: NonNull<T>,
+}
+
+// It is only a pointer, the value is used through `attach`.
+unsafe impl<T: Send> Send for DetachedBox<T> {}
+
+impl<'arena, T> ArenaBox<'arena, T> {
+    /// Lets go of the borrow of the arena, see [`DetachedBox`].
+    pub fn detach(this: Self) -> DetachedBox<T> {
+        DetachedBox {
+            ptr: unsafe { NonNull::new_unchecked(ArenaBox::into_raw(this)) },
+        }
+    }
+}
+
+impl<T> DetachedBox<T> {
+    /// Returns the box again, borrowing `arena`.
+    ///
+    /// # Safety
+    /// `arena` must be the Arena the value was allocated in,
+    /// and it must not have been reset or rewound since.
+    pub unsafe fn attach(self, arena: &Arena) -> ArenaBox<'_, T> {
+        let _ = arena;
+        ArenaBox::from_raw(self.ptr)
+    }
+}
+
+impl<T> fmt::Debug for DetachedBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DetachedBox").field(&self.ptr).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{Arena, ArenaBox, GlobalStore};
+
+    use alloc::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn test_round_trip() {
+        let mut arena = Arena::new();
+        let first = ArenaBox::detach(arena.alloc(1u32));
+
+        for n in 2..5u32 {
+            let (sent, boxed) = thread::scope(|threads| {
+                let sent = arena.into_send();
+                threads
+                    .spawn(move || {
+                        let arena = sent.into_inner();
+                        let boxed = ArenaBox::detach(arena.alloc([n as u8; 64]));
+                        (arena.into_send(), boxed)
+                    })
+                    .join()
+                    .unwrap()
+            });
+
+            arena = sent.into_inner();
+            let boxed = unsafe { boxed.attach(&arena) };
+            assert!(boxed.iter().all(|&b| u32::from(b) == n));
+            ArenaBox::into_raw(boxed);
+        }
+
+        assert_eq!(*unsafe { first.attach(&arena) }, 1);
+        assert_eq!(arena.allocated_bytes(), 4 + 3 * 64);
+    }
+
+    #[test]
+    fn test_drops_pending() {
+        let arena = Arena::new();
+        let shared = Rc::new(());
+
+        // An ArenaBox keeps what the region allocated before it.
+        let kept = arena.region(|s| {
+            s.alloc_with_drop(Rc::clone(&shared));
+            ArenaBox::detach(arena.alloc(1u32))
+        });
+        assert_eq!(Rc::strong_count(&shared), 2);
+
+        let arena = arena.into_send().into_inner();
+        assert_eq!(Rc::strong_count(&shared), 1);
+        assert_eq!(*unsafe { kept.attach(&arena) }, 1);
+    }
+
+    #[test]
+    #[should_panic = "can't be sent"]
+    fn test_own_store() {
+        Arena::builder()
+            .backing_store(GlobalStore)
+            .build()
+            .into_send();
+    }
+}
@@ -0,0 +1,598 @@
+use core::{
+    mem::{self, MaybeUninit},
+    ptr, slice,
+};
+
+use super::Scope;
+
+/// The initialized prefix of a slice that is being filled.
+/// Drops the elements written so far, unless it is turned into a slice.
+struct PartialSlice<T> {
+    ptr: *mut T,
+    len: usize,
+    cap: usize,
+}
+
+impl<T> PartialSlice<T> {
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn new(scope: &Scope<'_>, cap: usize) -> Self {
+        let ptr = scope.malloc::<T>(cap).expect("Allocation failed");
+        Self { ptr, len: 0, cap }
+    }
+
+    /// Moves the elements to a new allocation with room for
+    /// at least one more. The old allocation is not reused.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn grow(&mut self, scope: &Scope<'_>) {
+        if mem::size_of::<T>() == 0 {
+            self.cap = usize::MAX;
+            return;
+        }
+
+        let cap = self.cap.saturating_mul(2).max(4);
+        let ptr = scope.malloc::<T>(cap).expect("Allocation failed");
+
+        unsafe { ptr::copy_nonoverlapping(self.ptr, ptr, self.len) };
+        self.ptr = ptr;
+        self.cap = cap;
+    }
+
+    /// # Safety
+    /// There must be room for another element.
+    unsafe fn push(&mut self, value: T) {
+        self.ptr.add(self.len).write(value);
+        self.len += 1;
+    }
+
+    /// # Safety
+    /// The allocation must be valid for `'scope`.
+    unsafe fn into_slice<'scope>(self) -> &'scope mut [T] {
+        let slice = slice::from_raw_parts_mut(self.ptr, self.len);
+        mem::forget(self);
+        slice
+    }
+}
+
+impl<T> Drop for PartialSlice<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr, self.len)) }
+    }
+}
+
+impl<'scope> Scope<'scope> {
+    /// Copies `src` into the arena.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let copy = s.alloc_slice_copy(&[1, 2, 3]);
+    ///     copy[0] = 0;
+    ///
+    ///     assert_eq!(copy, [0, 2, 3]);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &'scope mut [T] {
+        unsafe {
+            let ptr = self.malloc::<T>(src.len()).expect("Allocation failed");
+            ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len());
+            slice::from_raw_parts_mut(ptr, src.len())
+        }
+    }
+
+    /// Allocates `len` `T`'s with all their bytes set to zero.
+    /// That is a valid value for many types, like integers,
+    /// but not for all of them.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::builder().zeroed(false).build();
+    ///
+    /// arena.region(|s| {
+    ///     let counts = s.alloc_zeroed_slice::<u32>(4);
+    ///     let counts = unsafe { &*(counts as *const _ as *const [u32]) };
+    ///
+    ///     assert_eq!(counts, [0; 4]);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_zeroed_slice<T>(&self, len: usize) -> &'scope mut [MaybeUninit<T>] {
+        unsafe {
+            let ptr = self
+                .malloc::<MaybeUninit<T>>(len)
+                .expect("Allocation failed");
+            ptr::write_bytes(ptr, 0, len);
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    /// Allocates `len` `T`'s that are zeroed, like
+    /// [`Scope::alloc_zeroed_slice`], but typed: `T` is
+    /// [`Zeroable`](bytemuck::Zeroable), so zero is a valid `T`.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let pixels = s.alloc_zeroable_slice::<[f32; 4]>(16);
+    ///     pixels[3][0] = 1.0;
+    ///
+    ///     assert_eq!(pixels.iter().map(|p| p[0]).sum::<f32>(), 1.0);
+    /// });
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_zeroable_slice<T: bytemuck::Zeroable>(&self, len: usize) -> &'scope mut [T] {
+        let zeroed = self.alloc_zeroed_slice::<T>(len);
+        unsafe { &mut *(zeroed as *mut [MaybeUninit<T>] as *mut [T]) }
+    }
+
+    /// Allocates room for `len` `T`'s, without initializing it.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let squares = s.alloc_uninit_slice::<u64>(8);
+    ///     for (i, square) in squares.iter_mut().enumerate() {
+    ///         square.write((i * i) as u64);
+    ///     }
+    ///
+    ///     assert_eq!(unsafe { squares[3].assume_init() }, 9);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_uninit_slice<T>(&self, len: usize) -> &'scope mut [MaybeUninit<T>] {
+        unsafe {
+            let ptr = self
+                .malloc::<MaybeUninit<T>>(len)
+                .expect("Allocation failed");
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    /// Clones the elements of `src` into the arena.
+    /// The clones are never dropped.
+    ///
+    /// If cloning panics, the clones made so far are dropped.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_slice_clone<T: Clone>(&self, src: &[T]) -> &'scope mut [T] {
+        let mut clones = PartialSlice::new(self, src.len());
+
+        for value in src {
+            unsafe { clones.push(value.clone()) };
+        }
+
+        unsafe { clones.into_slice() }
+    }
+
+    /// Allocates `len` elements, calling `f` with the index of each.
+    /// The elements are never dropped.
+    ///
+    /// If `f` panics, the elements made so far are dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let evens = s.alloc_slice_fill_with(4, |i| i * 2);
+    ///     assert_eq!(evens, [0, 2, 4, 6]);
+    ///
+    ///     let counts = s.alloc_slice_fill_default::<u32>(2);
+    ///     counts[0] += 3;
+    ///     assert_eq!(counts, [3, 0]);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_slice_fill_with<T, F>(&self, len: usize, mut f: F) -> &'scope mut [T]
+    where
+        F: FnMut(usize) -> T,
+    {
+        let mut elements = PartialSlice::new(self, len);
+
+        for i in 0..len {
+            unsafe { elements.push(f(i)) };
+        }
+
+        unsafe { elements.into_slice() }
+    }
+
+    /// Allocates `len` copies of `value`.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_slice_fill_copy<T: Copy>(&self, len: usize, value: T) -> &'scope mut [T] {
+        self.alloc_slice_fill_with(len, |_| value)
+    }
+
+    /// Allocates `len` elements set to `T::default()`.
+    /// The elements are never dropped.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_slice_fill_default<T: Default>(&self, len: usize) -> &'scope mut [T] {
+        self.alloc_slice_fill_with(len, |_| T::default())
+    }
+
+    /// Collects the items of `iter` into the arena.
+    /// The items are never dropped.
+    ///
+    /// Space for `size_hint().0` items is allocated up front, so an
+    /// [`ExactSizeIterator`] needs a single allocation. Otherwise the items
+    /// move to a twice as large allocation whenever they don't fit,
+    /// leaving the previous allocations unused.
+    ///
+    /// If the iterator panics, the items collected so far are dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let squares = s.alloc_iter((1..4).map(|n| n * n));
+    ///     assert_eq!(squares, [1, 4, 9]);
+    ///
+    ///     let odd = s.alloc_iter((0..10).filter(|n| n % 2 == 1));
+    ///     assert_eq!(odd, [1, 3, 5, 7, 9]);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_iter<T, I>(&self, iter: I) -> &'scope mut [T]
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        let mut items = PartialSlice::new(self, iter.size_hint().0);
+
+        for value in iter {
+            if items.len == items.cap {
+                items.grow(self);
+            }
+            unsafe { items.push(value) };
+        }
+
+        unsafe { items.into_slice() }
+    }
+
+    /// Collects the pairs of `iter` into two parallel slices, in one pass.
+    /// The values are never dropped.
+    ///
+    /// If the iterator yields fewer items than its `len`,
+    /// the slices are shorter. Extra items are not consumed.
+    /// If the iterator panics, the items collected
+    /// so far are dropped.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let (keys, values) = s.alloc_unzip((0..3).map(|n| (n, n * 10)));
+    ///
+    ///     assert_eq!(keys, [0, 1, 2]);
+    ///     assert_eq!(values, [0, 10, 20]);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_unzip<A, B, I>(&self, iter: I) -> (&'scope mut [A], &'scope mut [B])
+    where
+        I: IntoIterator<Item = (A, B)>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = iter.into_iter();
+        let len = iter.len();
+
+        let mut a = PartialSlice::new(self, len);
+        let mut b = PartialSlice::new(self, len);
+
+        for _ in 0..len {
+            match iter.next() {
+                Some((x, y)) => unsafe {
+                    a.push(x);
+                    b.push(y);
+                },
+                None => break,
+            }
+        }
+
+        unsafe { (a.into_slice(), b.into_slice()) }
+    }
+
+    /// Like [`Scope::alloc_unzip`], for triples.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_unzip3<A, B, C, I>(
+        &self,
+        iter: I,
+    ) -> (&'scope mut [A], &'scope mut [B], &'scope mut [C])
+    where
+        I: IntoIterator<Item = (A, B, C)>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = iter.into_iter();
+        let len = iter.len();
+
+        let mut a = PartialSlice::new(self, len);
+        let mut b = PartialSlice::new(self, len);
+        let mut c = PartialSlice::new(self, len);
+
+        for _ in 0..len {
+            match iter.next() {
+                Some((x, y, z)) => unsafe {
+                    a.push(x);
+                    b.push(y);
+                    c.push(z);
+                },
+                None => break,
+            }
+        }
+
+        unsafe { (a.into_slice(), b.into_slice(), c.into_slice()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arena::{Arena, ArenaRef},
+        testutil::DropCount,
+    };
+
+    use alloc::string::String;
+    use core::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn test_alloc_uninit_slice() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let a = s.alloc_uninit_slice::<u32>(3);
+            let b = s.alloc_uninit_slice::<u32>(3);
+            assert_eq!((a.len(), b.len()), (3, 3));
+            assert!(a.as_ptr_range().end <= b.as_ptr());
+
+            assert!(s.alloc_uninit_slice::<String>(0).is_empty());
+        });
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_alloc_zeroable_slice() {
+        let arena = Arena::builder().zeroed(false).build();
+
+        for _ in 0..2 {
+            arena.region(|s| {
+                let floats = s.alloc_zeroable_slice::<f64>(100);
+                assert!(floats.iter().all(|&f| f == 0.0));
+
+                // Dirties the memory for the next region.
+                floats.fill(f64::NAN);
+            });
+        }
+    }
+
+    #[test]
+    fn test_alloc_zeroed_slice() {
+        let arena = Arena::builder().zeroed(false).build();
+
+        for _ in 0..2 {
+            arena.region(|s| {
+                let bytes = s.alloc_zeroed_slice::<u8>(100);
+                assert!(bytes.iter().all(|b| unsafe { b.assume_init() } == 0));
+
+                // Dirties the memory for the next region.
+                for b in bytes {
+                    b.write(0xff);
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_alloc_slice() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let empty: &[u64] = s.alloc_slice_copy(&[]);
+            assert!(empty.is_empty());
+
+            let words = s.alloc_slice_copy(&[1u32, 2, 3]);
+            words[2] = 4;
+            assert_eq!(words, [1, 2, 4]);
+
+            let strings = [String::from("a"), String::from("b")];
+            let clones = s.alloc_slice_clone(&strings);
+            clones[0].push('!');
+            assert_eq!(clones, ["a!", "b"]);
+
+            // The clones are never dropped.
+            for clone in clones {
+                drop(core::mem::take(clone));
+            }
+        });
+    }
+
+    #[test]
+    fn test_alloc_slice_clone_panic() {
+        /// Panics on the 3rd clone.
+        struct Bomb<'a> {
+            clones: &'a Cell<usize>,
+            drops: &'a Cell<usize>,
+        }
+
+        impl Clone for Bomb<'_> {
+            fn clone(&self) -> Self {
+                self.clones.set(self.clones.get() + 1);
+                assert!(self.clones.get() < 3, "boom");
+                Bomb { ..*self }
+            }
+        }
+
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let clones = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+            let drops = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+            let src = s
+                .alloc_unzip((0..4).map(|_| (Bomb { clones, drops }, ())))
+                .0;
+
+            let src = &src[..];
+            let result = panic::catch_unwind(AssertUnwindSafe(|| s.alloc_slice_clone(src)));
+
+            assert!(result.is_err());
+            assert_eq!(drops.get(), 2);
+        });
+    }
+
+    #[test]
+    fn test_alloc_slice_fill() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let squares = s.alloc_slice_fill_with(5, |i| i * i);
+            assert_eq!(squares, [0, 1, 4, 9, 16]);
+
+            let ones = s.alloc_slice_fill_copy(3, 1u8);
+            assert_eq!(ones, [1; 3]);
+
+            let cells = s.alloc_slice_fill_default::<Cell<u32>>(2);
+            cells[1].set(5);
+            assert_eq!(cells[0].get() + cells[1].get(), 5);
+
+            assert!(s.alloc_slice_fill_copy(0, 0u64).is_empty());
+            assert_eq!(s.alloc_slice_fill_default::<()>(7).len(), 7);
+        });
+    }
+
+    #[test]
+    fn test_alloc_slice_fill_panic() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let drops = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                s.alloc_slice_fill_with(10, |i| {
+                    assert!(i < 4, "boom");
+                    DropCount(drops)
+                })
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(drops.get(), 4);
+        });
+    }
+
+    #[test]
+    fn test_alloc_iter() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let exact = s.alloc_iter(0..100u32);
+            assert!(exact.iter().copied().eq(0..100));
+            assert_eq!(arena.allocated_bytes(), 400);
+
+            let filtered = s.alloc_iter((0..1000u32).filter(|n| n % 10 == 0));
+            assert!(filtered.iter().copied().eq((0..100).map(|n| n * 10)));
+
+            let units = s.alloc_iter(core::iter::repeat(()).take_while(|_| true).take(10));
+            assert_eq!(units.len(), 10);
+
+            let empty = s.alloc_iter(core::iter::empty::<u64>());
+            assert!(empty.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_alloc_iter_panic() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let drops = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                s.alloc_iter((0..10).filter(|_| true).map(|n| {
+                    assert!(n < 7, "boom");
+                    DropCount(drops)
+                }))
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(drops.get(), 7);
+        });
+    }
+
+    #[test]
+    fn test_unzip() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let (a, b) = s.alloc_unzip((0..100u8).zip(100..200u32));
+            assert!(a.iter().copied().eq(0..100));
+            assert!(b.iter().copied().eq(100..200));
+
+            let (a, b, c) = s.alloc_unzip3((0..4).map(|n| (n, n as u16 * 2, [n; 3])));
+            assert_eq!(a, [0, 1, 2, 3]);
+            assert_eq!(b, [0, 2, 4, 6]);
+            assert_eq!(c[3], [3; 3]);
+        });
+    }
+
+    #[test]
+    fn test_unzip_short_iterator() {
+        let arena = Arena::new();
+
+        /// Claims to have 10 items, but only yields 3.
+        struct Liar(u32);
+
+        impl Iterator for Liar {
+            type Item = (u32, u32);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0 += 1;
+                (self.0 <= 3).then_some((self.0, self.0))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (10, Some(10))
+            }
+        }
+
+        impl ExactSizeIterator for Liar {}
+
+        arena.region(|s| {
+            let (a, b) = s.alloc_unzip(Liar(0));
+            assert_eq!((&*a, &*b), (&[1, 2, 3][..], &[1, 2, 3][..]));
+        });
+    }
+
+    #[test]
+    fn test_unzip_panic() {
+        let arena = Arena::new();
+        arena.region(|s| {
+            // The counters must outlive the Scope, so they live in the arena.
+            let left = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+            let right = ArenaRef::get(s.alloc_ref(Cell::new(0)));
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                s.alloc_unzip((0..10).map(|n| {
+                    if n == 4 {
+                        panic!("boom");
+                    }
+                    (DropCount(left), DropCount(right))
+                }))
+            }));
+
+            assert!(result.is_err());
+            assert_eq!((left.get(), right.get()), (4, 4));
+        });
+    }
+}
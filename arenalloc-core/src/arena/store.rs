@@ -0,0 +1,193 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use alloc::{
+    alloc::{alloc, alloc_zeroed, dealloc},
+    rc::Rc,
+};
+
+/// Where an Arena gets the memory for its buckets from,
+/// set with [`ArenaBuilder::backing_store`](super::ArenaBuilder::backing_store).
+/// By default that is the global allocator, see [`GlobalStore`].
+///
+/// # Safety
+/// The memory returned by `allocate` must be valid for `layout`,
+/// and stay valid until it is given to `deallocate`.
+/// ```
+/// use arenalloc_core::arena::{Arena, BackingStore, GlobalStore};
+/// use std::{alloc::Layout, cell::Cell, ptr::NonNull, rc::Rc};
+///
+/// #[derive(Default)]
+/// struct Counting {
+///     live: Cell<usize>,
+/// }
+///
+/// unsafe impl BackingStore for Counting {
+///     fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+///         self.live.set(self.live.get() + layout.size());
+///         GlobalStore.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         self.live.set(self.live.get() - layout.size());
+///         GlobalStore.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let store = Rc::new(Counting::default());
+/// let arena = Arena::builder().backing_store(store.clone()).build();
+/// assert!(store.live.get() > arena.capacity());
+///
+/// drop(arena);
+/// assert_eq!(store.live.get(), 0);
+/// ```
+pub unsafe trait BackingStore {
+    /// Allocates memory for `layout`, or returns `None` if that failed.
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Like [`BackingStore::allocate`], but the memory is zeroed.
+    fn allocate_zeroed(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let ptr = self.allocate(layout)?;
+        unsafe { ptr.as_ptr().write_bytes(0, layout.size()) };
+        Some(ptr)
+    }
+
+    /// Gives back the memory at `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by this store
+    /// for `layout`, and must not be used afterwards.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// Allocates buckets with the global allocator,
+/// which is what an Arena does by default.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GlobalStore;
+
+unsafe impl BackingStore for GlobalStore {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { alloc(layout) })
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { alloc_zeroed(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        dealloc(ptr.as_ptr(), layout)
+    }
+}
+
+unsafe impl<S: BackingStore + ?Sized> BackingStore for &S {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+}
+
+unsafe impl<S: BackingStore + ?Sized> BackingStore for Rc<S> {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+}
+
+/// Returns `store`, or the global allocator if there is none.
+pub(super) fn or_global(store: Option<&Rc<dyn BackingStore>>) -> &dyn BackingStore {
+    match store {
+        Some(store) => &**store,
+        None => &GlobalStore,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackingStore, GlobalStore};
+    use crate::arena::{AllocError, Arena};
+
+    use alloc::{boxed::Box, rc::Rc};
+    use core::{alloc::Layout, cell::Cell, mem::MaybeUninit, ptr::NonNull};
+
+    /// Hands out a single slab once.
+    struct Slab {
+        memory: Cell<Option<NonNull<u8>>>,
+        len: usize,
+    }
+
+    unsafe impl BackingStore for Slab {
+        fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+            let ptr = self.memory.get().filter(|_| layout.size() <= self.len)?;
+            self.memory.set(None);
+            Some(ptr)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, _: Layout) {
+            self.memory.set(Some(ptr));
+        }
+    }
+
+    #[test]
+    fn test_slab() {
+        let mut memory = Box::new([MaybeUninit::<u64>::uninit(); 64]);
+        let slab = Rc::new(Slab {
+            memory: Cell::new(NonNull::new(memory.as_mut_ptr() as *mut u8)),
+            len: 512,
+        });
+
+        let arena = Arena::builder()
+            .initial_capacity(256)
+            .backing_store(slab.clone())
+            .build();
+        assert!(slab.memory.get().is_none());
+
+        arena.region(|s| {
+            assert_eq!(s.alloc_zeroed_slice::<u8>(256).len(), 256);
+            assert!(matches!(
+                s.malloc::<u8>(1),
+                Err(AllocError::SystemAllocFailed)
+            ));
+        });
+
+        drop(arena);
+        assert!(slab.memory.get().is_some());
+    }
+
+    #[test]
+    fn test_allocate_zeroed() {
+        struct Dirty;
+
+        unsafe impl BackingStore for Dirty {
+            fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+                let ptr = GlobalStore.allocate(layout)?;
+                unsafe { ptr.as_ptr().write_bytes(0xAB, layout.size()) };
+                Some(ptr)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                GlobalStore.deallocate(ptr, layout)
+            }
+        }
+
+        let layout = Layout::new::<[u8; 32]>();
+        let ptr = Dirty.allocate_zeroed(layout).unwrap();
+
+        unsafe {
+            assert_eq!(*(ptr.as_ptr() as *const [u8; 32]), [0; 32]);
+            Dirty.deallocate(ptr, layout);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use super::{Arena, Scope};
+
+/// What [`Arena::stream`] measured.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// The number of inputs processed.
+    pub items: usize,
+
+    /// The most bytes allocated for a single input, including padding.
+    pub peak_bytes: usize,
+}
+
+impl Arena {
+    /// Calls `f` for every input, each in a fresh region,
+    /// and passes the results to `sink`.
+    ///
+    /// Everything allocated in a region is given back before the
+    /// next input, also if `f` panics, so the Arena never holds more
+    /// than the largest single input needed. The buckets are kept and reused.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let mut arena = Arena::with_limit(1024);
+    /// let mut lengths = Vec::new();
+    ///
+    /// let stats = arena.stream(
+    ///     ["a", "bb", "ccc"],
+    ///     |s, word| s.alloc_str(&word.repeat(100)).len(),
+    ///     |len| lengths.push(len),
+    /// );
+    ///
+    /// assert_eq!(lengths, [100, 200, 300]);
+    /// assert_eq!(stats.peak_bytes, 300);
+    /// ```
+    pub fn stream<I, R, F, S>(
+        &mut self,
+        inputs: impl IntoIterator<Item = I>,
+        mut f: F,
+        mut sink: S,
+    ) -> StreamStats
+    where
+        F: for<'scope> FnMut(&Scope<'scope>, I) -> R,
+        S: FnMut(R),
+    {
+        let mut stats = StreamStats::default();
+
+        for input in inputs {
+            let before = self.allocated_bytes();
+
+            let result = self.region(|s| {
+                let result = f(s, input);
+
+                let used = self.allocated_bytes() - before;
+                stats.peak_bytes = stats.peak_bytes.max(used);
+                result
+            });
+
+            stats.items += 1;
+            sink(result);
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{AllocError, Arena, ArenaRef};
+
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn test_stream_bounded() {
+        let mut arena = Arena::with_limit(4096);
+        let mut sums = Vec::new();
+
+        let stats = arena.stream(
+            0..1000u32,
+            |s, n| {
+                let (a, b) = s.alloc_unzip((0..100).map(|i| (i + n, i)));
+                a.iter().zip(&*b).map(|(a, b)| a - b).sum::<u32>()
+            },
+            |sum| sums.push(sum),
+        );
+
+        assert_eq!(stats.items, 1000);
+        assert_eq!(stats.peak_bytes, 800);
+        assert!(sums.iter().copied().eq((0..1000).map(|n| n * 100)));
+        assert_eq!(arena.allocated_bytes(), 0);
+        assert!(arena.capacity() <= 4096);
+    }
+
+    #[test]
+    fn test_stream_reuses_buckets() {
+        let mut arena = Arena::new();
+        let mut capacity = 0;
+
+        arena.stream(
+            0..10,
+            |s, _| {
+                for _ in 0..3 {
+                    s.malloc::<u8>(1000).unwrap();
+                }
+                s.arena().capacity()
+            },
+            |cap| {
+                if capacity == 0 {
+                    capacity = cap;
+                }
+                assert_eq!(cap, capacity);
+            },
+        );
+
+        assert_eq!(arena.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_stream_limit() {
+        let mut arena = Arena::with_limit(1024);
+
+        let mut results = Vec::new();
+        arena.stream([100, 2000, 100], |s, n| s.reserve(n), |r| results.push(r));
+
+        assert_eq!(
+            results,
+            [
+                Ok(()),
+                Err(AllocError::LimitExceeded {
+                    limit: 1024,
+                    requested: 2000
+                }),
+                Ok(())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_panic() {
+        let mut arena = Arena::new();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            arena.stream(
+                0..10,
+                |s, n| {
+                    let handled = ArenaRef::get(s.alloc_ref(Cell::new(false)));
+                    let id = s.alloc_handler(move |_: &()| handled.set(true));
+                    s.invoke(id, &());
+
+                    assert!(n < 3, "boom");
+                },
+                |_| {},
+            )
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(arena.allocated_bytes(), 0);
+        assert_eq!(arena.handlers().len(), 0);
+    }
+}
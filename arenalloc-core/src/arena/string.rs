@@ -0,0 +1,284 @@
+use core::{fmt, ptr, slice, str};
+
+use super::{arena::Claim, AllocError, Arena, Scope};
+
+impl<'scope> Scope<'scope> {
+    /// Copies `s` into the arena.
+    /// The empty string doesn't take any space.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     assert_eq!(s.alloc_str("hello"), "hello");
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_str(&self, s: &str) -> &'scope str {
+        self.try_alloc_str(s).expect("Allocation failed")
+    }
+
+    /// Like [`Scope::alloc_str`], but returns an error
+    /// if the string could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc_str(&self, s: &str) -> Result<&'scope str, AllocError> {
+        if s.is_empty() {
+            return Ok("");
+        }
+
+        unsafe {
+            let ptr = self.arena().malloc_bytes(s.len(), 1)?;
+
+            #[cfg(feature = "type-stats")]
+            self.arena().types.record("str", s.len());
+
+            #[cfg(feature = "debug-track")]
+            self.arena().track(core::alloc::Layout::for_value(s));
+
+            ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len());
+            Ok(str::from_utf8_unchecked(slice::from_raw_parts(
+                ptr,
+                s.len(),
+            )))
+        }
+    }
+
+    /// Formats `args` directly into the arena.
+    /// See also the [`arena_format!`](crate::arena_format) macro.
+    ///
+    /// The output is written into the free space of the current bucket,
+    /// and only moved to a new allocation if it doesn't fit.
+    /// Empty output doesn't take any space.
+    ///
+    /// # Panics
+    /// Panics if allocating fails, or if a formatting
+    /// trait implementation returns an error.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn alloc_fmt(&self, args: fmt::Arguments<'_>) -> &'scope str {
+        self.try_alloc_fmt(args).expect("Allocation failed")
+    }
+
+    /// Like [`Scope::alloc_fmt`], but returns an error if the output
+    /// could not be allocated. The space claimed so far is given back.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let line = 42;
+    ///     let message = s.try_alloc_fmt(format_args!("unexpected token on line {}", line));
+    ///     assert_eq!(message, Ok("unexpected token on line 42"));
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if a formatting trait implementation returns an error.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_alloc_fmt(&self, args: fmt::Arguments<'_>) -> Result<&'scope str, AllocError> {
+        if let Some(s) = args.as_str() {
+            return self.try_alloc_str(s);
+        }
+
+        let mut writer = ArenaWriter {
+            arena: self.arena(),
+            claim: self.arena().claim_rest(),
+            len: 0,
+            error: None,
+        };
+
+        if fmt::write(&mut writer, args).is_err() {
+            match writer.error {
+                Some(e) => {
+                    writer.arena.shrink(&mut writer.claim, 0);
+                    return Err(e);
+                }
+                None => panic!("a formatting trait implementation returned an error"),
+            }
+        }
+
+        Ok(writer.finish())
+    }
+}
+
+/// Writes into a claim of the arena,
+/// moving to a larger claim when full.
+struct ArenaWriter<'a> {
+    arena: &'a Arena,
+    claim: Claim,
+
+    /// The number of bytes written. These
+    /// are always valid UTF-8.
+    len: usize,
+
+    error: Option<AllocError>,
+}
+
+impl<'a> ArenaWriter<'a> {
+    /// Moves the written bytes to a claim with room for `additional` more.
+    fn relocate(&mut self, additional: usize) -> Result<(), AllocError> {
+        let needed = self
+            .len
+            .checked_add(additional)
+            .ok_or(AllocError::Overflow)?;
+        let len = needed.max(self.claim.len.saturating_mul(2)).max(64);
+
+        self.arena.shrink(&mut self.claim, self.len);
+        let claim = self.arena.claim(len)?;
+
+        unsafe { ptr::copy_nonoverlapping(self.claim.ptr, claim.ptr, self.len) };
+        self.claim = claim;
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn finish<'s>(mut self) -> &'s str {
+        self.arena.shrink(&mut self.claim, self.len);
+
+        #[cfg(feature = "type-stats")]
+        if self.len > 0 {
+            self.arena.types.record("str", self.len);
+        }
+
+        #[cfg(feature = "debug-track")]
+        self.arena
+            .track(unsafe { core::alloc::Layout::from_size_align_unchecked(self.len, 1) });
+
+        if self.len == 0 {
+            return "";
+        }
+
+        // Only whole `str`s were written.
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.claim.ptr, self.len)) }
+    }
+}
+
+impl fmt::Write for ArenaWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.claim.len - self.len < s.len() {
+            if let Err(e) = self.relocate(s.len()) {
+                self.error = Some(e);
+                return Err(fmt::Error);
+            }
+        }
+
+        unsafe { ptr::copy_nonoverlapping(s.as_ptr(), self.claim.ptr.add(self.len), s.len()) };
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+/// Formats into the arena of a Scope, returning a `&str`.
+/// See [`Scope::alloc_fmt`](crate::arena::Scope::alloc_fmt).
+/// ```
+/// use arenalloc_core::{arena::Arena, arena_format};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let x = 5;
+///     assert_eq!(arena_format!(s, "x = {}", x), "x = 5");
+/// });
+/// ```
+#[macro_export]
+macro_rules! arena_format {
+    ($scope:expr, $($arg:tt)*) => {
+        $scope.alloc_fmt(::core::format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{AllocError, Arena};
+
+    use core::fmt;
+
+    /// Writes `0` to `9`, `n` times, in separate writes.
+    struct Digits(usize);
+
+    impl fmt::Display for Digits {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for _ in 0..self.0 {
+                f.write_str("0123456789")?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_alloc_str() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let used = arena.allocated_bytes();
+            assert_eq!(s.alloc_str(""), "");
+            assert_eq!(arena_format!(s, "{}", ""), "");
+            assert_eq!(arena.allocated_bytes(), used);
+
+            assert_eq!(s.alloc_str("héllo"), "héllo");
+            assert_eq!(arena.allocated_bytes(), used + "héllo".len());
+        });
+    }
+
+    #[test]
+    fn test_alloc_fmt_spans_buckets() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let formatted = arena_format!(s, "{}ü", Digits(200));
+
+            assert_eq!(formatted.len(), 2002);
+            assert!(formatted.starts_with("0123456789"));
+            assert!(formatted.ends_with("9ü"));
+            assert_eq!(formatted.chars().filter(|&c| c == '5').count(), 200);
+        });
+    }
+
+    #[test]
+    fn test_alloc_fmt_fills_bucket() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            s.malloc::<u8>(512 - 30).unwrap();
+            let used = arena.allocated_bytes();
+
+            assert_eq!(arena_format!(s, "{}", Digits(3)).len(), 30);
+            assert_eq!(arena.allocated_bytes(), used + 30);
+            assert_eq!(arena.capacity(), 512);
+        });
+    }
+
+    #[test]
+    fn test_try_alloc_fmt() {
+        let arena = Arena::with_limit(512);
+
+        arena.region(|s| {
+            s.alloc_str("prefix");
+            let used = arena.allocated_bytes();
+
+            let result = s.try_alloc_fmt(format_args!("{}", Digits(60)));
+            assert!(matches!(result, Err(AllocError::LimitExceeded { .. })));
+            assert_eq!(arena.allocated_bytes(), used);
+
+            assert_eq!(
+                s.try_alloc_fmt(format_args!("{}", Digits(1))),
+                Ok("0123456789")
+            );
+            assert_eq!(arena.allocated_bytes(), used + 10);
+        });
+    }
+
+    #[test]
+    fn test_alloc_fmt_returns_space() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let a = arena_format!(s, "{}-{}", 1, 2);
+            let b = arena_format!(s, "{}", 3);
+
+            assert_eq!((a, b), ("1-2", "3"));
+            assert_eq!(arena.allocated_bytes(), 4);
+        });
+    }
+}
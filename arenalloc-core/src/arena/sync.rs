@@ -0,0 +1,458 @@
+use core::{
+    alloc::Layout,
+    hint, mem,
+    ptr::{self, NonNull},
+    slice,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+use alloc::alloc::{alloc, dealloc};
+
+use super::{builder::INITIAL_CAPACITY, AllocError};
+use crate::compat;
+
+/// The header of a bucket of a [`SyncArena`] or a [`Herd`](super::Herd),
+/// the data of the bucket directly follows it.
+#[repr(C)]
+pub(super) struct SyncBucket {
+    /// The number of bytes handed out, including padding.
+    used: AtomicUsize,
+
+    /// The number of bytes in the data.
+    capacity: usize,
+
+    /// The bucket that was full when this one was allocated.
+    prev: *mut SyncBucket,
+
+    /// The layout the bucket was allocated with.
+    layout: Layout,
+}
+
+impl SyncBucket {
+    /// Allocates a bucket with room for `capacity` bytes.
+    pub(super) fn new(capacity: usize, prev: *mut SyncBucket) -> Result<NonNull<Self>, AllocError> {
+        let data = Layout::array::<u8>(capacity).map_err(|_| AllocError::Overflow)?;
+        let (layout, _) = Layout::new::<Self>()
+            .extend(data)
+            .map_err(|_| AllocError::Overflow)?;
+        let layout = layout.pad_to_align();
+
+        unsafe {
+            let ptr =
+                NonNull::new(alloc(layout) as *mut Self).ok_or(AllocError::SystemAllocFailed)?;
+
+            ptr.as_ptr().write(Self {
+                used: AtomicUsize::new(0),
+                capacity,
+                prev,
+                layout,
+            });
+            Ok(ptr)
+        }
+    }
+
+    /// The capacity a new bucket needs to fit `size` bytes aligned to `align`,
+    /// regardless of the alignment of its start.
+    pub(super) fn room_for(size: usize, align: usize) -> Result<usize, AllocError> {
+        if align <= mem::align_of::<Self>() {
+            Ok(size)
+        } else {
+            size.checked_add(align - 1).ok_or(AllocError::Overflow)
+        }
+    }
+
+    /// Returns a pointer to the data of `this`,
+    /// derived from the pointer to the whole allocation.
+    unsafe fn data(this: *mut Self) -> *mut u8 {
+        (this as *mut u8).add(mem::size_of::<Self>())
+    }
+
+    /// Returns the start and end offsets of `size` bytes aligned to `align`
+    /// placed after the first `used` bytes, if they fit.
+    unsafe fn place(
+        this: *mut Self,
+        used: usize,
+        size: usize,
+        align: usize,
+    ) -> Option<(usize, usize)> {
+        let base = compat::addr(Self::data(this));
+
+        // `base + used` can't overflow, it is inside the bucket.
+        let start = ((base + used).checked_add(align - 1)? & !(align - 1)) - base;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= (*this).capacity)?;
+
+        Some((start, end))
+    }
+
+    /// Allocates `size` bytes aligned to `align`,
+    /// or returns `None` if they don't fit anymore.
+    unsafe fn malloc(this: *mut Self, size: usize, align: usize) -> Option<*mut u8> {
+        let header = &*this;
+        let mut used = header.used.load(Ordering::Relaxed);
+
+        loop {
+            let (start, end) = Self::place(this, used, size, align)?;
+
+            match header
+                .used
+                .compare_exchange_weak(used, end, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return Some(Self::data(this).add(start)),
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    /// Like [`SyncBucket::malloc`], for a bucket
+    /// no other thread allocates from.
+    pub(super) unsafe fn malloc_unshared(
+        this: *mut Self,
+        size: usize,
+        align: usize,
+    ) -> Option<*mut u8> {
+        let (start, end) = Self::place(this, (*this).used.load(Ordering::Relaxed), size, align)?;
+
+        (*this).used.store(end, Ordering::Relaxed);
+        Some(Self::data(this).add(start))
+    }
+
+    /// The number of bytes in the data of `this`.
+    pub(super) unsafe fn capacity(this: *mut Self) -> usize {
+        (*this).capacity
+    }
+
+    /// Returns `this` and the buckets before it, newest first.
+    pub(super) unsafe fn chain(this: *mut Self) -> impl Iterator<Item = *mut Self> {
+        let mut next = Some(this).filter(|bucket| !bucket.is_null());
+
+        core::iter::from_fn(move || {
+            let bucket = next?;
+            next = Some(unsafe { (*bucket).prev }).filter(|prev| !prev.is_null());
+            Some(bucket)
+        })
+    }
+
+    /// The number of bytes handed out by `this` and the buckets before it.
+    pub(super) unsafe fn chain_used(this: *mut Self) -> usize {
+        Self::chain(this)
+            .map(|bucket| (*bucket).used.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// The number of bytes reserved by `this` and the buckets before it.
+    pub(super) unsafe fn chain_capacity(this: *mut Self) -> usize {
+        Self::chain(this).map(|bucket| (*bucket).capacity).sum()
+    }
+
+    /// Deallocates `this` and the buckets before it.
+    pub(super) unsafe fn free_chain(mut this: *mut Self) {
+        while !this.is_null() {
+            let Self { prev, layout, .. } = this.read();
+            dealloc(this as *mut u8, layout);
+            this = prev;
+        }
+    }
+}
+
+/// An arena that can be shared between threads,
+/// for example to allocate in parallel and read
+/// the results afterwards.
+///
+/// Allocating bumps the index of the current bucket
+/// atomically, only growing takes a lock. The values
+/// are never dropped.
+/// ```
+/// use arenalloc_core::arena::SyncArena;
+/// use std::thread;
+///
+/// let arena = SyncArena::new();
+/// let arena = &arena;
+///
+/// let names = thread::scope(|scope| {
+///     let workers = (0..4)
+///         .map(|n| scope.spawn(move || &*arena.alloc_str(&format!("worker {}", n))))
+///         .collect::<Vec<_>>();
+///
+///     workers
+///         .into_iter()
+///         .map(|worker| worker.join().unwrap())
+///         .collect::<Vec<_>>()
+/// });
+///
+/// assert_eq!(names[3], "worker 3");
+/// ```
+pub struct SyncArena {
+    /// The bucket that is allocated from. It links to the buckets
+    /// before it. Null until a SyncArena made by
+    /// [`SyncArena::new`] allocates.
+    current: AtomicPtr<SyncBucket>,
+
+    /// Held while a new bucket is allocated.
+    growing: AtomicBool,
+}
+
+// Every byte is handed out only once, and the
+// buckets are only freed when the arena is dropped.
+unsafe impl Send for SyncArena {}
+unsafe impl Sync for SyncArena {}
+
+impl SyncArena {
+    /// Creates a SyncArena without buckets, the first
+    /// one is allocated by the first allocation.
+    ///
+    /// This is a `const fn`, so the SyncArena can be a `static`.
+    /// ```
+    /// use arenalloc_core::arena::SyncArena;
+    /// use std::thread;
+    ///
+    /// static INTERNED: SyncArena = SyncArena::new();
+    ///
+    /// let name = thread::spawn(|| &*INTERNED.alloc_str("config"))
+    ///     .join()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(name, "config");
+    /// assert_eq!(INTERNED.capacity(), 512);
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            current: AtomicPtr::new(ptr::null_mut()),
+            growing: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a SyncArena whose first bucket has room for `bytes`.
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self::try_with_capacity(bytes).expect("Allocation failed")
+    }
+
+    /// Like [`SyncArena::with_capacity`], but returns an error
+    /// if the first bucket could not be allocated.
+    pub fn try_with_capacity(bytes: usize) -> Result<Self, AllocError> {
+        let bucket = SyncBucket::new(bytes, ptr::null_mut())?;
+
+        Ok(Self {
+            current: AtomicPtr::new(bucket.as_ptr()),
+            growing: AtomicBool::new(false),
+        })
+    }
+
+    /// Moves `value` into the arena, and returns a mutable reference to it.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        self.try_alloc(value).expect("Allocation failed")
+    }
+
+    /// Like [`SyncArena::alloc`], but returns an error
+    /// if the value could not be allocated.
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_alloc<T>(&self, value: T) -> Result<&mut T, AllocError> {
+        unsafe {
+            let ptr = self.malloc_bytes(mem::size_of::<T>(), mem::align_of::<T>())? as *mut T;
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    /// Copies `src` into the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &mut [T] {
+        let size = mem::size_of_val(src);
+
+        unsafe {
+            let ptr = self
+                .malloc_bytes(size, mem::align_of::<T>())
+                .expect("Allocation failed") as *mut T;
+            ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len());
+            slice::from_raw_parts_mut(ptr, src.len())
+        }
+    }
+
+    /// Copies `s` into the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, s: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(s.as_bytes());
+        unsafe { core::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Allocates uninitialized memory for `layout`.
+    pub fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.malloc_bytes(layout.size(), layout.align())?;
+        Ok(unsafe { NonNull::new_unchecked(ptr) })
+    }
+
+    /// The number of bytes handed out by all buckets,
+    /// including padding, at the moment it is called.
+    pub fn allocated_bytes(&self) -> usize {
+        unsafe { SyncBucket::chain_used(self.current.load(Ordering::Acquire)) }
+    }
+
+    /// The total number of bytes reserved by the buckets.
+    pub fn capacity(&self) -> usize {
+        unsafe { SyncBucket::chain_capacity(self.current.load(Ordering::Acquire)) }
+    }
+
+    fn malloc_bytes(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        if size == 0 {
+            return Ok(compat::dangling_mut(align));
+        }
+
+        loop {
+            let bucket = self.current.load(Ordering::Acquire);
+
+            if !bucket.is_null() {
+                if let Some(ptr) = unsafe { SyncBucket::malloc(bucket, size, align) } {
+                    return Ok(ptr);
+                }
+            }
+
+            self.grow(bucket, SyncBucket::room_for(size, align)?)?;
+        }
+    }
+
+    /// Replaces the bucket `full` by a new one with room for
+    /// `needed` bytes, unless another thread already did.
+    /// `full` is null if there are no buckets yet.
+    #[cold]
+    fn grow(&self, full: *mut SyncBucket, needed: usize) -> Result<(), AllocError> {
+        let _lock = SpinLock::acquire(&self.growing);
+
+        if self.current.load(Ordering::Relaxed) != full {
+            return Ok(());
+        }
+
+        let capacity = if full.is_null() {
+            INITIAL_CAPACITY.max(needed)
+        } else {
+            unsafe { (*full).capacity }.saturating_mul(2).max(needed)
+        };
+        let bucket = SyncBucket::new(capacity, full)?;
+
+        self.current.store(bucket.as_ptr(), Ordering::Release);
+        Ok(())
+    }
+}
+
+impl Default for SyncArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SyncArena {
+    fn drop(&mut self) {
+        unsafe { SyncBucket::free_chain(*self.current.get_mut()) }
+    }
+}
+
+/// Holds a spin lock until it is dropped.
+pub(super) struct SpinLock<'a> {
+    flag: &'a AtomicBool,
+}
+
+impl<'a> SpinLock<'a> {
+    pub(super) fn acquire(flag: &'a AtomicBool) -> Self {
+        while flag
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+
+        Self { flag }
+    }
+}
+
+impl Drop for SpinLock<'_> {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyncArena;
+
+    use core::alloc::Layout;
+    use std::{sync::Barrier, thread, vec::Vec};
+
+    #[test]
+    fn test_parallel_alloc() {
+        const THREADS: usize = 4;
+        const PER_THREAD: usize = if cfg!(miri) { 50 } else { 2000 };
+
+        let arena = SyncArena::with_capacity(64);
+        let barrier = Barrier::new(THREADS);
+
+        let results = thread::scope(|scope| {
+            let workers = (0..THREADS)
+                .map(|t| {
+                    let (arena, barrier) = (&arena, &barrier);
+
+                    scope.spawn(move || {
+                        barrier.wait();
+                        (0..PER_THREAD)
+                            .map(|n| &*arena.alloc((t, n as u64)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for (t, values) in results.iter().enumerate() {
+            for (n, &&value) in values.iter().enumerate() {
+                assert_eq!(value, (t, n as u64));
+            }
+        }
+        assert_eq!(arena.allocated_bytes(), THREADS * PER_THREAD * 16);
+        assert!(arena.capacity() >= arena.allocated_bytes());
+    }
+
+    #[test]
+    fn test_lazy_first_bucket() {
+        static ARENA: SyncArena = SyncArena::new();
+
+        assert_eq!(ARENA.capacity(), 0);
+        assert_eq!(ARENA.allocated_bytes(), 0);
+        assert!(ARENA.alloc_slice_copy::<u8>(&[]).is_empty());
+        assert_eq!(ARENA.capacity(), 0);
+
+        let values = thread::scope(|scope| {
+            let workers = (0..4u64)
+                .map(|n| scope.spawn(move || &*ARENA.alloc([n; 100])))
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for (n, value) in (0..).zip(values) {
+            assert!(value.iter().all(|&v| v == n));
+        }
+        assert_eq!(ARENA.allocated_bytes(), 4 * 800);
+    }
+
+    #[test]
+    fn test_alloc_layout() {
+        let arena = SyncArena::with_capacity(16);
+
+        for &align in &[1, 8, 64, 4096] {
+            let layout = Layout::from_size_align(10, align).unwrap();
+            let ptr = arena.alloc_layout(layout).unwrap();
+            assert_eq!(crate::compat::addr(ptr.as_ptr()) % align, 0);
+        }
+
+        let empty = arena.alloc_slice_copy::<u64>(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(arena.alloc_str("abc"), "abc");
+    }
+}
@@ -0,0 +1,151 @@
+//! Allocation-site tracking behind the `debug-track` feature.
+//!
+//! The allocating methods of `Arena` and `Scope` are `#[track_caller]`
+//! with this feature, so every allocation is recorded under the
+//! location in your code that made it. Allocations through the
+//! `Allocator` traits are recorded under the implementation in this
+//! crate, as the collection calling it is in between.
+
+use core::{alloc::Layout, cell::RefCell, panic::Location};
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use super::Arena;
+
+/// The allocations made at one location, see [`Arena::allocation_report`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SiteStats {
+    /// Where the allocations were made.
+    pub location: &'static Location<'static>,
+
+    /// The number of allocations.
+    pub allocations: usize,
+
+    /// The bytes requested, without alignment padding.
+    pub bytes: usize,
+
+    /// The layout of the largest allocation.
+    pub largest: Layout,
+}
+
+/// The allocations of every location so far.
+/// The map is created by the first allocation, as
+/// `BTreeMap::new` is not `const` on the MSRV.
+pub(super) struct SiteTable {
+    sites: RefCell<Option<BTreeMap<&'static Location<'static>, SiteStats>>>,
+}
+
+impl SiteTable {
+    pub(super) const fn new() -> Self {
+        Self {
+            sites: RefCell::new(None),
+        }
+    }
+}
+
+impl Arena {
+    /// Records an allocation of `layout` at the location of the caller.
+    /// Allocations of zero bytes take no room, and are not recorded.
+    #[track_caller]
+    pub(super) fn track(&self, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+
+        let location = Location::caller();
+        let mut sites = self.sites.sites.borrow_mut();
+        let site = sites
+            .get_or_insert_with(BTreeMap::new)
+            .entry(location)
+            .or_insert(SiteStats {
+                location,
+                allocations: 0,
+                bytes: 0,
+                largest: layout,
+            });
+
+        site.allocations += 1;
+        site.bytes += layout.size();
+        if layout.size() > site.largest.size() {
+            site.largest = layout;
+        }
+    }
+
+    /// Returns the allocations made so far per location in
+    /// the source, sorted by bytes, largest first. To find the
+    /// code that makes an arena grow, without a profiler.
+    ///
+    /// Allocations that were given back are still counted.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     for n in 0..100u64 {
+    ///         s.alloc(n);
+    ///     }
+    ///     s.alloc_str("a header");
+    /// });
+    ///
+    /// let report = arena.allocation_report();
+    /// assert_eq!(report[0].allocations, 100);
+    /// assert_eq!(report[0].bytes, 800);
+    /// assert_eq!(report[0].location.file(), file!());
+    /// ```
+    pub fn allocation_report(&self) -> Vec<SiteStats> {
+        let mut report = self
+            .sites
+            .sites
+            .borrow()
+            .iter()
+            .flat_map(|sites| sites.values().copied())
+            .collect::<Vec<_>>();
+
+        report.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.location.cmp(b.location)));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        arena::{Arena, ArenaRef},
+        arena_format,
+    };
+
+    use core::{alloc::Layout, panic::Location};
+
+    #[test]
+    fn test_allocation_report() {
+        let arena = Arena::new();
+        let line = Location::caller().line();
+
+        arena.region(|s| {
+            for n in 0..10u32 {
+                s.alloc_slice_fill_copy(n as usize, n);
+            }
+            ArenaRef::get(s.alloc_ref([0u8; 3]));
+            s.alloc_back(1u16);
+            arena_format!(s, "{}", 12345);
+            s.alloc(());
+        });
+        let boxed = arena.alloc(7u64);
+
+        let report = arena.allocation_report();
+        let lines = report
+            .iter()
+            .map(|site| {
+                assert_eq!(site.location.file(), file!());
+                (site.location.line() - line, site.allocations, site.bytes)
+            })
+            .collect::<alloc::vec::Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [(4, 9, 180), (11, 1, 8), (8, 1, 5), (6, 1, 3), (7, 1, 2)]
+        );
+        assert_eq!(report[0].largest, Layout::array::<u32>(9).unwrap());
+        assert_eq!(*boxed, 7);
+    }
+}
@@ -0,0 +1,146 @@
+use core::{cell::RefCell, cmp, mem};
+
+use alloc::vec::Vec;
+
+/// The number of bytes of the first chunk.
+const FIRST_CHUNK_BYTES: usize = 512;
+
+/// An arena that only allocates `T`'s. Unlike [`Arena`](super::Arena),
+/// it drops the values when it is dropped, and it can iterate
+/// over the values allocated so far.
+///
+/// The values are stored in chunks, every chunk twice as
+/// large as the previous one. Values never move.
+/// ```
+/// use arenalloc_core::arena::TypedArena;
+///
+/// let mut arena = TypedArena::new();
+///
+/// let a = arena.alloc(String::from("a"));
+/// let b = arena.alloc(String::from("b"));
+/// a.push_str(b);
+///
+/// assert_eq!(arena.iter().collect::<Vec<_>>(), ["ab", "b"]);
+/// ```
+pub struct TypedArena<T> {
+    /// The chunk values are pushed into.
+    /// It never grows beyond its capacity, so the values don't move.
+    current: RefCell<Vec<T>>,
+
+    /// The full chunks, oldest first.
+    rest: RefCell<Vec<Vec<T>>>,
+}
+
+impl<T> TypedArena<T> {
+    pub fn new() -> Self {
+        let capacity = cmp::max(1, FIRST_CHUNK_BYTES / cmp::max(1, mem::size_of::<T>()));
+        Self::with_capacity(capacity)
+    }
+
+    /// Creates a TypedArena with room for `capacity` values in its first chunk.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            current: RefCell::new(Vec::with_capacity(cmp::max(1, capacity))),
+            rest: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Moves `value` into the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc(&self, value: T) -> &mut T {
+        let mut current = self.current.borrow_mut();
+
+        if current.len() == current.capacity() {
+            let next = Vec::with_capacity(current.capacity().saturating_mul(2));
+            let full = mem::replace(&mut *current, next);
+            self.rest.borrow_mut().push(full);
+        }
+
+        current.push(value);
+
+        // The value stays in place until the arena is dropped,
+        // because `current` is never pushed to beyond its capacity.
+        unsafe { &mut *current.as_mut_ptr().add(current.len() - 1) }
+    }
+
+    /// The number of values allocated.
+    pub fn len(&self) -> usize {
+        let rest = self.rest.borrow().iter().map(Vec::len).sum::<usize>();
+        rest + self.current.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the values, in allocation order.
+    /// This takes `&mut self`, because `alloc` hands out mutable references.
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> + '_ {
+        let rest = self.rest.get_mut().iter().flatten();
+        rest.chain(self.current.get_mut().iter())
+    }
+
+    /// Iterates mutably over the values, in allocation order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        let rest = self.rest.get_mut().iter_mut().flatten();
+        rest.chain(self.current.get_mut().iter_mut())
+    }
+}
+
+impl<T> Default for TypedArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypedArena;
+    use crate::testutil::DropCount;
+
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+
+    #[test]
+    fn test_values_stay_in_place() {
+        let mut arena = TypedArena::with_capacity(1);
+
+        let refs = (0..100u64).map(|n| arena.alloc(n)).collect::<Vec<_>>();
+        for r in refs {
+            *r *= 2;
+        }
+
+        assert_eq!(arena.len(), 100);
+        assert!(arena.iter().copied().eq((0..100).map(|n| n * 2)));
+
+        for n in arena.iter_mut() {
+            *n += 1;
+        }
+        assert!(arena.iter().copied().eq((0..100).map(|n| n * 2 + 1)));
+    }
+
+    #[test]
+    fn test_drops() {
+        let drops = Cell::new(0);
+
+        let arena = TypedArena::new();
+        for _ in 0..1000 {
+            arena.alloc(DropCount(&drops));
+        }
+
+        assert_eq!(drops.get(), 0);
+        drop(arena);
+        assert_eq!(drops.get(), 1000);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut arena = TypedArena::new();
+
+        for _ in 0..1000 {
+            arena.alloc(());
+        }
+
+        assert_eq!(arena.iter().count(), 1000);
+    }
+}
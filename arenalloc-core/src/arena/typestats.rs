@@ -0,0 +1,144 @@
+//! Per-type allocation statistics behind the `type-stats` feature.
+
+use core::cell::RefCell;
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use super::Arena;
+
+/// The allocations of one type, see [`Arena::stats_by_type`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TypeStats {
+    /// The name of the type, `[T]` for slices of `T`.
+    pub type_name: &'static str,
+
+    /// The number of allocations.
+    pub allocations: usize,
+
+    /// The bytes of the values, without alignment padding.
+    pub bytes: usize,
+}
+
+/// The statistics of every type allocated so far.
+/// The map is created by the first allocation, as
+/// `BTreeMap::new` is not `const` on the MSRV.
+pub(super) struct TypeTable {
+    types: RefCell<Option<BTreeMap<&'static str, (usize, usize)>>>,
+}
+
+impl TypeTable {
+    pub(super) const fn new() -> Self {
+        Self {
+            types: RefCell::new(None),
+        }
+    }
+
+    pub(super) fn record(&self, type_name: &'static str, bytes: usize) {
+        let mut types = self.types.borrow_mut();
+        let (allocations, total) = types
+            .get_or_insert_with(BTreeMap::new)
+            .entry(type_name)
+            .or_insert((0, 0));
+
+        *allocations += 1;
+        *total += bytes;
+    }
+}
+
+impl Arena {
+    /// Returns the allocations made through typed entry points, like
+    /// [`Scope::malloc`](super::Scope::malloc) and [`Arena::alloc`],
+    /// per type and sorted by bytes, largest first.
+    ///
+    /// An allocation of `n` `T`'s counts as a `T` if `n` is 1,
+    /// and as a `[T]` otherwise.
+    /// ```
+    /// use arenalloc_core::arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     s.alloc_ref(1u64);
+    ///     s.malloc::<u32>(4).unwrap();
+    /// });
+    ///
+    /// let stats = arena.stats_by_type();
+    /// assert_eq!(stats[0].type_name, "[u32]");
+    /// assert_eq!(stats[0].bytes, 16);
+    /// assert_eq!(stats[1].type_name, "u64");
+    /// ```
+    pub fn stats_by_type(&self) -> Vec<TypeStats> {
+        let mut stats = self
+            .types
+            .types
+            .borrow()
+            .iter()
+            .flatten()
+            .map(|(&type_name, &(allocations, bytes))| TypeStats {
+                type_name,
+                allocations,
+                bytes,
+            })
+            .collect::<Vec<_>>();
+
+        stats.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.type_name.cmp(b.type_name)));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeStats;
+    use crate::arena::Arena;
+
+    #[test]
+    fn test_stats_by_type() {
+        let arena = Arena::new();
+
+        let allocated = arena.region(|s| {
+            s.alloc_ref(1u8);
+            for n in 0..10u64 {
+                s.alloc_ref(n);
+            }
+            s.malloc::<u16>(3).unwrap();
+            s.malloc::<u16>(5).unwrap();
+            s.alloc_str("hello");
+            s.alloc_unzip((0..4).map(|n: u32| (n, [n as u8; 3])));
+            arena.allocated_bytes()
+        });
+
+        let stats = arena.stats_by_type();
+        let stat = |type_name, allocations, bytes| TypeStats {
+            type_name,
+            allocations,
+            bytes,
+        };
+
+        assert_eq!(
+            stats,
+            [
+                stat("u64", 10, 80),
+                stat("[u16]", 2, 16),
+                stat("[u32]", 1, 16),
+                stat("[[u8; 3]]", 1, 12),
+                stat("str", 1, 5),
+                stat("u8", 1, 1),
+            ]
+        );
+
+        // 7 bytes before the first `u64`, 3 before the `u32`s.
+        let bytes = stats.iter().map(|stat| stat.bytes).sum::<usize>();
+        assert_eq!(allocated, bytes + 10);
+    }
+
+    #[test]
+    fn test_alloc_fmt_counts_used_bytes() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            crate::arena_format!(s, "{}-{}", 1, 2);
+        });
+
+        assert_eq!(arena.stats_by_type()[0].bytes, 3);
+    }
+}
@@ -0,0 +1,127 @@
+//! Deterministic allocation workloads, as `(size, align)` pairs,
+//! to feed to [`Arena::alloc_bytes_black_box`](super::Arena::alloc_bytes_black_box).
+//! The sequences never change between versions of the crate,
+//! so benchmarks of different versions run identical workloads.
+
+/// `n` allocations cycling through 1 to 64 bytes,
+/// with alignments cycling through 1, 2, 4 and 8.
+pub fn many_small(n: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..n).map(|i| (i % 64 + 1, 1 << (i % 4)))
+}
+
+/// An endless pseudo random mix, determined by `seed`.
+/// One in 16 allocations is between 1 and 16 KiB, aligned to 8,
+/// the others are between 1 and 128 bytes, aligned to 1 to 16.
+pub fn mixed(seed: u64) -> Mixed {
+    // Xorshift gets stuck on zero.
+    Mixed {
+        state: seed ^ 0x9E37_79B9_7F4A_7C15,
+    }
+}
+
+/// The iterator returned by [`mixed`].
+#[derive(Debug, Clone)]
+pub struct Mixed {
+    state: u64,
+}
+
+impl Mixed {
+    /// xorshift64*
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+impl Iterator for Mixed {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.next_u64();
+        let size = (n >> 32) as usize;
+
+        if n % 16 == 0 {
+            Some((size % (15 * 1024) + 1024, 8))
+        } else {
+            Some((size % 128 + 1, 1 << ((n >> 8) % 5)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{many_small, mixed};
+    use crate::{
+        arena::{Arena, Counters},
+        compat,
+    };
+
+    use alloc::vec::Vec;
+
+    /// Makes sure the large allocations of `mixed` fit in the next bucket.
+    fn arena() -> Arena {
+        let arena = Arena::new();
+        arena.reserve(16 * 1024).unwrap();
+        arena
+    }
+
+    fn run(workload: impl Iterator<Item = (usize, usize)>) -> Arena {
+        let arena = arena();
+        arena.reset_counters();
+        let mut bytes = 0;
+        let mut allocations = 0;
+
+        for (size, align) in workload {
+            let ptr = arena.alloc_bytes_black_box(size, align);
+            assert_eq!(compat::addr(ptr) % align, 0);
+
+            bytes += size;
+            allocations += 1;
+        }
+
+        arena.check_invariants();
+        let counters = arena.counters();
+        assert_eq!((counters.allocations, counters.bytes), (allocations, bytes));
+        assert!(counters.buckets > 0);
+        arena
+    }
+
+    #[test]
+    fn test_many_small() {
+        let arena = run(many_small(10_000));
+
+        arena.reset_counters();
+        assert_eq!(arena.counters(), Counters::default());
+    }
+
+    #[test]
+    fn test_mixed() {
+        run(mixed(0).take(2_000));
+        run(mixed(42).take(2_000));
+
+        let a = mixed(7).take(100).collect::<Vec<_>>();
+        assert_eq!(a, mixed(7).take(100).collect::<Vec<_>>());
+        assert_ne!(a, mixed(8).take(100).collect::<Vec<_>>());
+        assert!(a.iter().any(|&(size, _)| size >= 1024));
+    }
+
+    #[test]
+    fn test_stream_invariants() {
+        let mut arena = arena();
+
+        arena.stream(
+            0..10u64,
+            |s, seed| {
+                for (size, align) in mixed(seed).take(100) {
+                    s.arena().alloc_bytes_black_box(size, align);
+                }
+            },
+            |_| {},
+        );
+
+        arena.check_invariants();
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+}
@@ -0,0 +1,53 @@
+//! Fallbacks for APIs that are newer than the MSRV.
+//! `build.rs` decides which formulation gets compiled.
+
+/// Returns the address of `ptr`, without exposing
+/// its provenance when the compiler supports it.
+#[inline]
+pub(crate) fn addr<T>(ptr: *const T) -> usize {
+    // Only compiled when `build.rs` detected a new enough compiler.
+    #[cfg(arenalloc_strict_provenance)]
+    #[allow(clippy::incompatible_msrv)]
+    {
+        ptr.addr()
+    }
+
+    #[cfg(not(arenalloc_strict_provenance))]
+    {
+        ptr as usize
+    }
+}
+
+/// Returns a pointer to `addr` without provenance,
+/// which is only valid for zero-sized accesses.
+#[inline]
+pub(crate) fn dangling_mut(addr: usize) -> *mut u8 {
+    #[cfg(arenalloc_strict_provenance)]
+    #[allow(clippy::incompatible_msrv)]
+    {
+        core::ptr::without_provenance_mut(addr)
+    }
+
+    #[cfg(not(arenalloc_strict_provenance))]
+    {
+        addr as *mut u8
+    }
+}
+
+/// Keeps the optimizer from reasoning about `value`.
+#[cfg(feature = "bench-support")]
+#[inline]
+pub(crate) fn black_box<T>(value: T) -> T {
+    #[cfg(arenalloc_hint_black_box)]
+    #[allow(clippy::incompatible_msrv)]
+    {
+        core::hint::black_box(value)
+    }
+
+    #[cfg(not(arenalloc_hint_black_box))]
+    unsafe {
+        let copy = core::ptr::read_volatile(&value);
+        core::mem::forget(value);
+        copy
+    }
+}
@@ -0,0 +1,42 @@
+//! The allocator of [arenalloc]: `Arena`, `Scope` and
+//! the types they hand out, without the collections.
+//! Use this crate directly to avoid compiling the
+//! collections, or to depend on a more stable API.
+//!
+//! # Minimum supported Rust version
+//!
+//! The MSRV is Rust 1.65, with and without features.
+//! Newer compiler APIs are detected by `build.rs`
+//! and used when available, with a fallback for
+//! older compilers.
+//!
+//! | feature          | minimum Rust |
+//! |------------------|--------------|
+//! | `debug-poison`   | 1.65         |
+//! | `type-stats`     | 1.65         |
+//! | `debug-track`    | 1.65         |
+//! | `bench-support`  | 1.65         |
+//! | `allocator-api2` | 1.65         |
+//! | `std`            | 1.65         |
+//! | `tracing`        | 1.65         |
+//! | `mmap`           | 1.65         |
+//! | `sanitizers`     | 1.65         |
+//! | `bytemuck`       | 1.65         |
+//! | `nightly`        | nightly      |
+//!
+//! The `nightly` feature implements the unstable
+//! `Allocator` trait for `Arena` and `Scope`.
+//!
+//! [arenalloc]: https://crates.io/crates/arenalloc
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+extern crate alloc;
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+mod compat;
+
+pub mod arena;
+#[cfg(test)]
+mod testutil;
@@ -0,0 +1,13 @@
+//! Helpers for the tests of both crates. `arenalloc`
+//! includes this file as a module of its own.
+
+use core::cell::Cell;
+
+/// Counts how often it is dropped.
+pub struct DropCount<'a>(pub &'a Cell<usize>);
+
+impl Drop for DropCount<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
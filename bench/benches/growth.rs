@@ -0,0 +1,81 @@
+//! Compares growth factors on a few representative workloads.
+//!
+//! Criterion measures the allocation throughput, the bytes
+//! reserved by the arena at the end of every workload
+//! are printed once per factor, as criterion can't report them.
+//! ```skip
+//! cargo bench --manifest-path bench/Cargo.toml --bench growth
+//! ```
+
+use arenalloc::arena::{Arena, Scope};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+type Workload = fn(&Scope<'_>);
+
+const FACTORS: &[(usize, usize)] = &[(5, 4), (3, 2), (2, 1), (3, 1)];
+
+/// 10_000 allocations of a single u64.
+fn many_small(s: &Scope<'_>) {
+    for _ in 0..10_000 {
+        black_box(s.malloc::<u64>(1).unwrap());
+    }
+}
+
+/// 2_000 allocations cycling through 1 to 256 bytes,
+/// with alignments of 1, 4 and 8.
+fn mixed(s: &Scope<'_>) {
+    for n in 0..2_000usize {
+        let bytes = n % 256 + 1;
+
+        match n % 3 {
+            0 => black_box(s.malloc::<u8>(bytes).unwrap()),
+            1 => black_box(s.malloc::<u32>(bytes / 4 + 1).unwrap() as *mut u8),
+            _ => black_box(s.malloc::<u64>(bytes / 8 + 1).unwrap() as *mut u8),
+        };
+    }
+}
+
+/// 32 allocations of 16 KiB.
+fn few_large(s: &Scope<'_>) {
+    const SIZE: usize = 16 * 1024;
+
+    for _ in 0..32 {
+        s.reserve(SIZE).unwrap();
+        black_box(s.malloc::<u8>(SIZE).unwrap());
+    }
+}
+
+fn growth(c: &mut Criterion) {
+    let workloads: &[(&str, Workload)] = &[
+        ("many-small", many_small),
+        ("mixed", mixed),
+        ("few-large", few_large),
+    ];
+
+    for &(name, workload) in workloads {
+        let mut group = c.benchmark_group(name);
+
+        for &(num, den) in FACTORS {
+            let build = move || Arena::builder().growth_factor(num, den).build();
+
+            let arena = build();
+            arena.region(workload);
+            eprintln!(
+                "{} {}/{}: {} bytes reserved",
+                name,
+                num,
+                den,
+                arena.capacity()
+            );
+
+            group.bench_function(format!("{}/{}", num, den), |b| {
+                b.iter_batched(build, |arena| arena.region(workload), BatchSize::SmallInput)
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, growth);
+criterion_main!(benches);
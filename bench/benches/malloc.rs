@@ -0,0 +1,36 @@
+//! Measures the allocation hot path.
+//! Run on two commits and let criterion
+//! compare the saved baselines:
+//! ```skip
+//! cargo bench --manifest-path bench/Cargo.toml -- --save-baseline before
+//! cargo bench --manifest-path bench/Cargo.toml -- --baseline before
+//! ```
+
+use arenalloc::arena::Arena;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+const ALLOCATIONS: u64 = 10_000;
+
+fn malloc_u64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("malloc");
+    group.throughput(Throughput::Elements(ALLOCATIONS));
+
+    group.bench_function("u64", |b| {
+        b.iter_batched(
+            Arena::new,
+            |arena| {
+                arena.region(|s| {
+                    for _ in 0..ALLOCATIONS {
+                        black_box(s.malloc::<u64>(1).unwrap());
+                    }
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, malloc_u64);
+criterion_main!(benches);
@@ -1,3 +0,0 @@
-mod arena;
-mod bucket;
-pub use arena::*;
@@ -0,0 +1,146 @@
+//! Collecting iterators into the arena, like `collect`
+//! does into the collections of `std`.
+//! ```
+//! use arenalloc::{
+//!     arena::Arena,
+//!     collections::{collect::CollectIn, localstring::LocalString, localvec::LocalVec},
+//! };
+//!
+//! let arena = Arena::new();
+//!
+//! arena.region(|s| {
+//!     let squares = (1..4).map(|n| n * n).collect_in::<LocalVec<_>>(s);
+//!     assert_eq!(*squares, [1, 4, 9]);
+//!
+//!     let shout = "hey".chars().map(|c| c.to_ascii_uppercase()).collect_in::<LocalString>(s);
+//!     assert_eq!(shout, "HEY");
+//!
+//!     let odd: &[u32] = (0..10).filter(|n| n % 2 == 1).collect_in(s);
+//!     assert_eq!(odd, [1, 3, 5, 7, 9]);
+//! });
+//! ```
+
+use super::{localbox::LocalBox, localstring::LocalString, localvec::LocalVec};
+use crate::arena::Scope;
+
+/// A collection that can be built from an iterator in the
+/// arena of a Scope, the counterpart of `FromIterator`.
+pub trait FromIteratorIn<'a, 'scope, A>: Sized {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = A>;
+}
+
+/// Adds [`collect_in`](CollectIn::collect_in) to every iterator.
+pub trait CollectIn: Iterator + Sized {
+    /// Collects the items into a collection in the arena of `scope`.
+    fn collect_in<'a, 'scope, C>(self, scope: &'a Scope<'scope>) -> C
+    where
+        C: FromIteratorIn<'a, 'scope, Self::Item>,
+    {
+        C::from_iter_in(self, scope)
+    }
+}
+
+impl<I: Iterator> CollectIn for I {}
+
+impl<'a, 'scope, T> FromIteratorIn<'a, 'scope, T> for LocalVec<'a, 'scope, T> {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut v = LocalVec::new(scope);
+        v.extend(iter);
+        v
+    }
+}
+
+impl<'a, 'scope, T> FromIteratorIn<'a, 'scope, T> for LocalBox<'a, 'scope, [T]> {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        LocalBox::from_iter(scope, iter)
+    }
+}
+
+impl<'a, 'scope> FromIteratorIn<'a, 'scope, char> for LocalString<'a, 'scope> {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut s = LocalString::new(scope);
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a, 'scope, 's> FromIteratorIn<'a, 'scope, &'s str> for LocalString<'a, 'scope> {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = &'s str>,
+    {
+        let mut s = LocalString::new(scope);
+        s.extend(iter);
+        s
+    }
+}
+
+/// See [`Scope::alloc_iter`]. The items are never dropped.
+impl<'a, 'scope, T> FromIteratorIn<'a, 'scope, T> for &'scope mut [T] {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        scope.alloc_iter(iter)
+    }
+}
+
+/// See [`Scope::alloc_iter`]. The items are never dropped.
+impl<'a, 'scope, T> FromIteratorIn<'a, 'scope, T> for &'scope [T] {
+    fn from_iter_in<I>(iter: I, scope: &'a Scope<'scope>) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        scope.alloc_iter(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollectIn;
+    use crate::{
+        arena::Arena,
+        collections::{localbox::LocalBox, localstring::LocalString, localvec::LocalVec},
+    };
+
+    use alloc::string::String;
+
+    #[test]
+    fn test_collect_in() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let words = "one two three".split(' ');
+
+            let joined = words.clone().collect_in::<LocalString>(s);
+            assert_eq!(joined, "onetwothree");
+
+            let owned = words
+                .clone()
+                .map(String::from)
+                .collect_in::<LocalBox<[_]>>(s);
+            assert_eq!(owned[2], "three");
+
+            let lengths = words.map(str::len).collect_in::<LocalVec<_>>(s);
+            assert_eq!(*lengths, [3, 3, 5]);
+
+            let doubled: &mut [usize] = lengths.iter().map(|n| n * 2).collect_in(s);
+            doubled.reverse();
+            assert_eq!(doubled, [10, 6, 6]);
+
+            let empty = core::iter::empty::<char>().collect_in::<LocalString>(s);
+            assert!(empty.is_empty());
+        });
+    }
+}
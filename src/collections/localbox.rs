@@ -1,31 +1,388 @@
-use crate::arena::Scope;
+use super::localvec::LocalVec;
+use crate::arena::{AllocError, Arena, ArenaBox, Scope};
 
 use core::{
+    alloc::Layout,
+    any::Any,
+    borrow::{Borrow, BorrowMut},
+    cmp::Ordering,
+    fmt,
+    future::Future,
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    mem::{self, MaybeUninit},
     ops::{Deref, DerefMut},
+    pin::Pin,
+    ptr::{self, NonNull},
+    task::{Context, Poll},
 };
 
-pub struct LocalBox<'a, 'scope, T> {
-    scope: PhantomData<&'a Scope<'scope>>,
+/// A pointer to a value in the arena that
+/// owns it, and drops it when it goes out of scope.
+pub struct LocalBox<'a, 'scope, T: ?Sized> {
+    scope: &'a Scope<'scope>,
     pointer: *mut T,
+    marker: PhantomData<T>,
 }
 
 impl<'a, 'scope, T> LocalBox<'a, 'scope, T> {
+    #[cfg_attr(feature = "debug-track", track_caller)]
     pub fn new(scope: &'a Scope<'scope>, value: T) -> Self {
+        Self::try_new(scope, value).expect("Allocation failed")
+    }
+
+    /// Like [`LocalBox::new`], but returns an error
+    /// if the value could not be allocated.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn try_new(scope: &'a Scope<'scope>, value: T) -> Result<Self, AllocError> {
         let ptr = unsafe {
-            let ptr = scope.malloc::<T>(1).expect("Allocation failed");
+            let ptr = scope.malloc::<T>(1)?;
             ptr.write(value);
             ptr
         };
 
+        Ok(unsafe { Self::from_ptr(scope, ptr) })
+    }
+
+    /// Moves `value` into the arena and pins it there.
+    /// Pinned futures can be polled through the box.
+    ///
+    /// The value stays at its address until the box is dropped.
+    ///
+    /// # Safety
+    /// The box must not be forgotten, with [`mem::forget`] or a cycle.
+    /// The region would take the memory back without dropping the
+    /// value, which breaks the drop guarantee of [`Pin`].
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub unsafe fn pin(scope: &'a Scope<'scope>, value: T) -> Pin<Self> {
+        unsafe { Pin::new_unchecked(Self::new(scope, value)) }
+    }
+
+    /// Allocates space for a `T` first, and then moves the result
+    /// of `f` into it, see [`Scope::alloc_with`].
+    #[inline(always)]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn new_with<F>(scope: &'a Scope<'scope>, f: F) -> Self
+    where
+        F: FnOnce() -> T,
+    {
+        let ptr = scope.malloc::<T>(1).expect("Allocation failed");
+
+        unsafe {
+            ptr.write(f());
+            Self::from_ptr(scope, ptr)
+        }
+    }
+
+    /// Allocates space for a `T` without initializing it.
+    /// The memory may contain anything.
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let mut five = LocalBox::<u32>::new_uninit(s);
+    ///     five.write(5);
+    ///
+    ///     let five = unsafe { five.assume_init() };
+    ///     assert_eq!(*five, 5);
+    /// });
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn new_uninit(scope: &'a Scope<'scope>) -> LocalBox<'a, 'scope, MaybeUninit<T>> {
+        let ptr = scope
+            .malloc::<MaybeUninit<T>>(1)
+            .expect("Allocation failed");
+
+        unsafe { LocalBox::from_ptr(scope, ptr) }
+    }
+
+    /// Allocates space for `len` `T`'s without initializing them.
+    /// The memory may contain anything.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn new_uninit_slice(
+        scope: &'a Scope<'scope>,
+        len: usize,
+    ) -> LocalBox<'a, 'scope, [MaybeUninit<T>]> {
+        let ptr = scope
+            .malloc::<MaybeUninit<T>>(len)
+            .expect("Allocation failed");
+
+        unsafe { LocalBox::from_ptr(scope, ptr::slice_from_raw_parts_mut(ptr, len)) }
+    }
+}
+
+impl<'a, 'scope, T> LocalBox<'a, 'scope, T> {
+    /// Moves the value into `arena` storage that outlives the Scope,
+    /// so it can be returned from the region.
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// let promoted = arena.region(|s| {
+    ///     let mut b = LocalBox::new(s, vec![1, 2]);
+    ///     b.push(3);
+    ///     LocalBox::promote(b, &arena)
+    /// });
+    ///
+    /// assert_eq!(*promoted, [1, 2, 3]);
+    /// ```
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn promote<'arena>(this: Self, arena: &'arena Arena) -> ArenaBox<'arena, T> {
+        let value = unsafe { this.pointer.read() };
+        mem::forget(this);
+        arena.alloc(value)
+    }
+}
+
+impl<'a, 'scope, T: ?Sized> LocalBox<'a, 'scope, T> {
+    /// # Safety
+    /// `pointer` must point to an initialized `T` in the
+    /// arena of the Scope, which nothing else owns.
+    pub(crate) unsafe fn from_ptr(scope: &'a Scope<'scope>, pointer: *mut T) -> Self {
         Self {
-            scope: PhantomData,
-            pointer: ptr,
+            scope,
+            pointer,
+            marker: PhantomData,
         }
     }
+
+    /// Consumes the box, and returns a pointer to the value.
+    /// The value is not dropped, unless the pointer
+    /// is turned back into a box with [`LocalBox::from_raw`].
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let ptr = LocalBox::into_raw(LocalBox::new(s, String::from("hi")));
+    ///     let b = unsafe { LocalBox::from_raw(s, ptr) };
+    ///     assert_eq!(*b, "hi");
+    /// });
+    /// ```
+    pub fn into_raw(this: Self) -> *mut T {
+        let pointer = this.pointer;
+        mem::forget(this);
+        pointer
+    }
+
+    /// Takes ownership of the value at `ptr` again.
+    ///
+    /// # Safety
+    /// `ptr` must come from [`LocalBox::into_raw`] on a box of
+    /// `scope`, and must not be turned into a box twice.
+    pub unsafe fn from_raw(scope: &'a Scope<'scope>, ptr: *mut T) -> Self {
+        Self::from_ptr(scope, ptr)
+    }
+
+    /// Consumes the box, and returns a reference to the
+    /// value that is valid for the whole Scope.
+    /// The value is never dropped.
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let first: &mut u32;
+    ///     {
+    ///         let b = LocalBox::new(s, 1);
+    ///         first = LocalBox::leak(b);
+    ///     }
+    ///     *first += 1;
+    ///     assert_eq!(*first, 2);
+    /// });
+    /// ```
+    pub fn leak(this: Self) -> &'scope mut T {
+        unsafe { &mut *Self::into_raw(this) }
+    }
+}
+
+impl<'a, 'scope, T> LocalBox<'a, 'scope, [T]> {
+    /// Clones the elements of `src` into a boxed slice.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn from_slice(scope: &'a Scope<'scope>, src: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        let mut v = LocalVec::with_capacity(scope, src.len());
+        v.extend(src.iter().cloned());
+        v.into_boxed_slice()
+    }
+
+    /// Collects `iter` into a boxed slice.
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let words = LocalBox::from_iter(s, "a b c".split(' ').map(String::from));
+    ///     assert_eq!(&*words, ["a", "b", "c"]);
+    /// });
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn from_iter<I>(scope: &'a Scope<'scope>, iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut v = LocalVec::new(scope);
+        v.extend(iter);
+        v.into_boxed_slice()
+    }
+}
+
+impl<'a, 'scope> LocalBox<'a, 'scope, str> {
+    /// Copies `s` into a boxed `str`.
+    #[allow(clippy::should_implement_trait)]
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn from_str(scope: &'a Scope<'scope>, s: &str) -> Self {
+        let ptr = scope.malloc::<u8>(s.len()).expect("Allocation failed");
+
+        unsafe {
+            ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len());
+            Self::from_ptr(
+                scope,
+                ptr::slice_from_raw_parts_mut(ptr, s.len()) as *mut str,
+            )
+        }
+    }
+}
+
+impl<'a, 'scope, T> LocalBox<'a, 'scope, MaybeUninit<T>> {
+    /// Converts to `LocalBox<T>`.
+    ///
+    /// # Safety
+    /// The value must be initialized, see [`MaybeUninit::assume_init`].
+    pub unsafe fn assume_init(self) -> LocalBox<'a, 'scope, T> {
+        let (scope, pointer) = (self.scope, self.pointer as *mut T);
+        mem::forget(self);
+        LocalBox::from_ptr(scope, pointer)
+    }
+}
+
+impl<'a, 'scope, T> LocalBox<'a, 'scope, [MaybeUninit<T>]> {
+    /// Converts to `LocalBox<[T]>`.
+    ///
+    /// # Safety
+    /// Every element must be initialized, see [`MaybeUninit::assume_init`].
+    pub unsafe fn assume_init(self) -> LocalBox<'a, 'scope, [T]> {
+        let (scope, pointer) = (self.scope, self.pointer as *mut [T]);
+        mem::forget(self);
+        LocalBox::from_ptr(scope, pointer)
+    }
+}
+
+impl<'a, 'scope, T: ?Sized> LocalBox<'a, 'scope, T> {
+    /// Converts to a box of an unsized type, like a trait object,
+    /// by coercing the reference `f` is called with.
+    /// With the `nightly` feature the conversion also happens implicitly.
+    /// ```
+    /// use arenalloc::{arena::Arena, collections::localbox::LocalBox};
+    /// use core::fmt::Display;
+    ///
+    /// let arena = Arena::new();
+    ///
+    /// arena.region(|s| {
+    ///     let items: [LocalBox<dyn Display>; 2] = [
+    ///         LocalBox::coerce(LocalBox::new(s, 1), |n| n as &mut dyn Display),
+    ///         LocalBox::coerce(LocalBox::new(s, "two"), |n| n as &mut dyn Display),
+    ///     ];
+    ///
+    ///     assert_eq!(format!("{} {}", &*items[0], &*items[1]), "1 two");
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `f` returns a reference to anything
+    /// but the start of the value.
+    pub fn coerce<U, F>(mut this: Self, f: F) -> LocalBox<'a, 'scope, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let pointer = f(&mut this) as *mut U;
+        assert_eq!(
+            pointer as *mut u8, this.pointer as *mut u8,
+            "the coerced reference must point to the boxed value"
+        );
+
+        let scope = this.scope;
+        mem::forget(this);
+        unsafe { LocalBox::from_ptr(scope, pointer) }
+    }
+}
+
+/// Implements downcasting for a box of a `dyn Any` type.
+macro_rules! impl_downcast {
+    ($($Any:tt)+) => {
+        impl<'a, 'scope> LocalBox<'a, 'scope, $($Any)+> {
+            /// Converts to a box of the concrete type,
+            /// or gives the box back if the value is another type.
+            pub fn downcast<T: Any>(self) -> Result<LocalBox<'a, 'scope, T>, Self> {
+                if self.is::<T>() {
+                    let scope = self.scope;
+                    let pointer = Self::into_raw(self) as *mut T;
+                    Ok(unsafe { LocalBox::from_ptr(scope, pointer) })
+                } else {
+                    Err(self)
+                }
+            }
+
+            /// Returns a reference to the value if it is a `T`.
+            pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+                (**self).downcast_ref()
+            }
+
+            /// Returns a mutable reference to the value if it is a `T`.
+            pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+                (**self).downcast_mut()
+            }
+        }
+    };
+}
+
+impl_downcast!(dyn Any);
+impl_downcast!(dyn Any + Send);
+impl_downcast!(dyn Any + Send + Sync);
+
+#[cfg(feature = "nightly")]
+impl<'a, 'scope, T, U> core::ops::CoerceUnsized<LocalBox<'a, 'scope, U>> for LocalBox<'a, 'scope, T>
+where
+    T: ?Sized + core::marker::Unsize<U>,
+    U: ?Sized,
+{
 }
 
-impl<'a, 'scope, T> Deref for LocalBox<'a, 'scope, T> {
+/// Drops the value, and gives its memory back
+/// if nothing was allocated after it.
+impl<'a, 'scope, T: ?Sized> Drop for LocalBox<'a, 'scope, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let layout = Layout::for_value(&*self.pointer);
+            ptr::drop_in_place(self.pointer);
+            self.scope
+                .dealloc_last(NonNull::new_unchecked(self.pointer as *mut u8), layout);
+        }
+    }
+}
+
+// Like `Box`, moving the box never moves the value.
+impl<T: ?Sized> Unpin for LocalBox<'_, '_, T> {}
+
+impl<F: ?Sized + Future + Unpin> Future for LocalBox<'_, '_, F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        F::poll(Pin::new(&mut **self), cx)
+    }
+}
+
+impl<'a, 'scope, T: ?Sized> Deref for LocalBox<'a, 'scope, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -33,8 +390,534 @@ impl<'a, 'scope, T> Deref for LocalBox<'a, 'scope, T> {
     }
 }
 
-impl<'a, 'scope, T> DerefMut for LocalBox<'a, 'scope, T> {
+impl<'a, 'scope, T: ?Sized> DerefMut for LocalBox<'a, 'scope, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { &mut *(self.pointer) }
     }
 }
+
+/// Clones the value into a new box in the same Scope.
+impl<T: Clone> Clone for LocalBox<'_, '_, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.scope, (**self).clone())
+    }
+}
+
+impl<T: Clone> Clone for LocalBox<'_, '_, [T]> {
+    fn clone(&self) -> Self {
+        Self::from_slice(self.scope, self)
+    }
+}
+
+impl Clone for LocalBox<'_, '_, str> {
+    fn clone(&self) -> Self {
+        Self::from_str(self.scope, self)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalBox<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for LocalBox<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for LocalBox<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.pointer, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for LocalBox<'_, '_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for LocalBox<'_, '_, T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for LocalBox<'_, '_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for LocalBox<'_, '_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for LocalBox<'_, '_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for LocalBox<'_, '_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsMut<T> for LocalBox<'_, '_, T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for LocalBox<'_, '_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> BorrowMut<T> for LocalBox<'_, '_, T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalBox;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::{collections::BTreeSet, format, string::String, vec};
+    use core::{
+        any::Any,
+        cell::Cell,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    struct Counted<'a> {
+        drops: DropCount<'a>,
+        value: u32,
+    }
+
+    /// Miri reports the heap memory of the values if it leaks.
+    #[test]
+    fn test_drop_heap_values() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let _string = LocalBox::new(s, String::from("owned"));
+            let _vec = LocalBox::new(s, vec![String::from("a"), String::from("b")]);
+            let _nested = LocalBox::new(
+                s,
+                vec![Counted {
+                    drops: DropCount(&drops),
+                    value: 0,
+                }],
+            );
+        });
+
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_promote() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        let promoted = arena.region(|s| {
+            let b = LocalBox::new(
+                s,
+                Counted {
+                    drops: DropCount(&drops),
+                    value: 7,
+                },
+            );
+            LocalBox::promote(b, &arena)
+        });
+
+        assert_eq!(promoted.value, 7);
+        assert_eq!(drops.get(), 0);
+
+        drop(promoted);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_new_with() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            s.reserve(4 * 8192).unwrap();
+            let large = LocalBox::new_with(s, || [1u32; 8192]);
+            assert_eq!(large.iter().sum::<u32>(), 8192);
+
+            let counted = LocalBox::new_with(s, || Counted {
+                drops: DropCount(&drops),
+                value: 1,
+            });
+            assert_eq!(counted.value, 1);
+        });
+
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_unsized() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut slice = LocalBox::from_slice(s, &[1, 2, 3]);
+            slice[0] = 0;
+            assert_eq!(&*slice, [0, 2, 3]);
+
+            let counted = LocalBox::from_iter(
+                s,
+                (0..5).map(|value| Counted {
+                    drops: DropCount(&drops),
+                    value,
+                }),
+            );
+            assert_eq!(counted.len(), 5);
+            assert_eq!(counted[4].value, 4);
+
+            let mut text = LocalBox::from_str(s, "hello");
+            text.make_ascii_uppercase();
+            assert_eq!(&*text, "HELLO");
+            assert_eq!(&*LocalBox::from_str(s, ""), "");
+        });
+
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_raw() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let counted = LocalBox::new(
+                s,
+                Counted {
+                    drops: DropCount(&drops),
+                    value: 3,
+                },
+            );
+            let ptr = LocalBox::into_raw(counted);
+
+            unsafe {
+                (*ptr).value += 1;
+                let counted = LocalBox::from_raw(s, ptr);
+                assert_eq!(counted.value, 4);
+            }
+            assert_eq!(drops.get(), 1);
+
+            let leaked = LocalBox::leak(LocalBox::from_slice(s, &[1, 2, 3]));
+            leaked[2] = 4;
+            assert_eq!(leaked, [1, 2, 4]);
+        });
+    }
+
+    trait Shape {
+        fn area(&self) -> u32;
+    }
+
+    impl Shape for (u32, u32) {
+        fn area(&self) -> u32 {
+            self.0 * self.1
+        }
+    }
+
+    impl Shape for Counted<'_> {
+        fn area(&self) -> u32 {
+            self.value
+        }
+    }
+
+    #[test]
+    fn test_coerce() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let shapes: [LocalBox<dyn Shape>; 2] = [
+                LocalBox::coerce(LocalBox::new(s, (2u32, 3u32)), |b| b as &mut dyn Shape),
+                LocalBox::coerce(
+                    LocalBox::new(
+                        s,
+                        Counted {
+                            drops: DropCount(&drops),
+                            value: 4,
+                        },
+                    ),
+                    |b| b as &mut dyn Shape,
+                ),
+            ];
+
+            assert_eq!(shapes.iter().map(|s| s.area()).sum::<u32>(), 10);
+        });
+
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the coerced reference must point to the boxed value")]
+    fn test_coerce_other_field() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let pair = LocalBox::new(s, (1u32, 2u32));
+            LocalBox::coerce(pair, |p| &mut p.1);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn test_coerce_unsized() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let shape: LocalBox<dyn Shape> = LocalBox::new(s, (4u32, 5u32));
+            let slice: LocalBox<[u8]> = LocalBox::new(s, [1, 2, 3]);
+
+            assert_eq!(shape.area(), 20);
+            assert_eq!(slice.len(), 3);
+        });
+    }
+
+    /// Polls `future` until it is ready.
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        use core::task::{RawWaker, RawWakerVTable, Waker};
+
+        fn noop_raw() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                noop_raw()
+            }
+            fn noop(_: *const ()) {}
+
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+
+        let waker = unsafe { Waker::from_raw(noop_raw()) };
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Returns `Pending` once before completing.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_pin_future() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let counted = Counted {
+                drops: DropCount(&drops),
+                value: 2,
+            };
+            let future = unsafe {
+                LocalBox::pin(s, async move {
+                    YieldOnce(false).await;
+                    counted.value * 21
+                })
+            };
+
+            assert_eq!(block_on(future), 42);
+            assert_eq!(drops.get(), 1);
+
+            let unpin = LocalBox::new(s, YieldOnce(false));
+            block_on(unpin);
+        });
+    }
+
+    #[test]
+    fn test_deref() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut a = LocalBox::new(s, 1u8);
+            let mut b = LocalBox::new(s, 2u64);
+            let c = LocalBox::new(s, [3u32; 4]);
+
+            *a += 1;
+            *b += 1;
+
+            assert_eq!(*a, 2);
+            assert_eq!(*b, 3);
+            assert_eq!(*c, [3; 4]);
+        });
+    }
+
+    #[test]
+    fn test_deref_across_buckets() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut first = LocalBox::new(s, 0u64);
+
+            let boxes = (0..200u64)
+                .map(|n| LocalBox::new(s, n))
+                .collect::<alloc::vec::Vec<_>>();
+
+            *first += 1;
+
+            assert_eq!(*first, 1);
+            assert!(boxes.iter().enumerate().all(|(n, b)| **b == n as u64));
+        });
+    }
+
+    #[test]
+    fn test_try_new_limit() {
+        let arena = Arena::with_limit(512);
+
+        arena.region(|s| {
+            let _first = LocalBox::try_new(s, [0u8; 256]).unwrap();
+            let _second = LocalBox::try_new(s, [0u8; 200]).unwrap();
+
+            assert!(LocalBox::try_new(s, [0u8; 100]).is_err());
+            assert!(LocalBox::try_new(s, 0u8).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_uninit() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut uninit = LocalBox::<Counted>::new_uninit(s);
+
+            unsafe {
+                let ptr = uninit.as_mut_ptr();
+                core::ptr::addr_of_mut!((*ptr).drops).write(DropCount(&drops));
+                core::ptr::addr_of_mut!((*ptr).value).write(7);
+
+                let init = uninit.assume_init();
+                assert_eq!(init.value, 7);
+                assert_eq!(drops.get(), 0);
+            }
+            assert_eq!(drops.get(), 1);
+
+            drop(LocalBox::<Counted>::new_uninit(s));
+            assert_eq!(drops.get(), 1);
+        });
+    }
+
+    #[test]
+    fn test_uninit_slice() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut uninit = LocalBox::<Counted>::new_uninit_slice(s, 3);
+            assert_eq!(uninit.len(), 3);
+
+            for (value, slot) in uninit.iter_mut().enumerate() {
+                slot.write(Counted {
+                    drops: DropCount(&drops),
+                    value: value as u32,
+                });
+            }
+
+            let init = unsafe { uninit.assume_init() };
+            assert_eq!(init.iter().map(|d| d.value).sum::<u32>(), 3);
+
+            drop(init);
+            assert_eq!(drops.get(), 3);
+        });
+    }
+
+    #[test]
+    fn test_traits() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let a = LocalBox::new(s, String::from("a"));
+            let b = a.clone();
+            assert_eq!(a, b);
+            assert_ne!(a.as_ptr(), b.as_ptr());
+            assert_eq!(format!("{:?} {}", a, b), "\"a\" a");
+
+            let words: BTreeSet<_> = ["b", "c", "a"]
+                .iter()
+                .map(|word| LocalBox::from_str(s, word))
+                .collect();
+            assert!(words.contains("a"));
+            assert_eq!(words.iter().next().map(|word| &**word), Some("a"));
+
+            let small = LocalBox::from_slice(s, &[1, 2]);
+            let large = LocalBox::from_slice(s, &[1, 3]);
+            assert!(small < large);
+            assert_eq!(small.clone().as_ref(), [1, 2]);
+        });
+    }
+
+    #[test]
+    fn test_downcast() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut number = LocalBox::coerce(LocalBox::new(s, 1u32), |n| n as &mut dyn Any);
+            *number.downcast_mut::<u32>().unwrap() += 1;
+            assert!(number.downcast_ref::<u64>().is_none());
+
+            let number = number.downcast::<u64>().unwrap_err();
+            assert_eq!(*number.downcast::<u32>().unwrap(), 2);
+
+            let text = LocalBox::coerce(LocalBox::new(s, String::from("a")), |n| {
+                n as &mut (dyn Any + Send)
+            });
+            assert_eq!(text.downcast_ref::<String>().unwrap(), "a");
+            assert_eq!(*text.downcast::<String>().unwrap(), "a");
+        });
+    }
+
+    #[test]
+    fn test_gives_back_last() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let kept = LocalBox::new(s, 1u64);
+            let used = arena.allocated_bytes();
+
+            for n in 0..100 {
+                let temporary = LocalBox::from_slice(s, &[n; 16]);
+                assert_eq!(temporary[15], n);
+            }
+            assert_eq!(arena.allocated_bytes(), used);
+
+            let number = LocalBox::coerce(LocalBox::new(s, 2u32), |n| n as &mut dyn Any);
+            drop(number);
+            assert_eq!(arena.allocated_bytes(), used);
+
+            // Not the last allocation, so its memory is kept.
+            let _after = LocalBox::new(s, 3u8);
+            drop(kept);
+            assert_eq!(arena.allocated_bytes(), used + 1);
+        });
+    }
+}
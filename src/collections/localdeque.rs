@@ -0,0 +1,509 @@
+use crate::arena::Scope;
+
+use core::{
+    cmp, fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem,
+    ops::{Index, IndexMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A double-ended queue in a ring buffer that lives in the arena,
+/// for scratch queues like the worklist of a graph search.
+///
+/// When it runs out of room the buffer grows in place if it is the
+/// last allocation of the arena. Otherwise the elements are moved to
+/// a new buffer twice as large, and the old buffer is not reused.
+/// ```
+/// use arenalloc::{arena::Arena, collections::localdeque::LocalVecDeque};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+///     let mut seen = [false; 4];
+///     let mut queue = LocalVecDeque::new(s);
+///     queue.push_back(0);
+///
+///     let mut order = Vec::new();
+///     while let Some(node) = queue.pop_front() {
+///         if !std::mem::replace(&mut seen[node], true) {
+///             order.push(node);
+///             queue.extend(edges.iter().filter(|e| e.0 == node).map(|e| e.1));
+///         }
+///     }
+///
+///     assert_eq!(order, [0, 1, 2, 3]);
+/// });
+/// ```
+pub struct LocalVecDeque<'a, 'scope, T> {
+    scope: &'a Scope<'scope>,
+    ptr: NonNull<T>,
+
+    /// The slot of the front element.
+    head: usize,
+    len: usize,
+    cap: usize,
+
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'scope, T> LocalVecDeque<'a, 'scope, T> {
+    pub fn new(scope: &'a Scope<'scope>) -> Self {
+        let cap = if mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            0
+        };
+
+        Self {
+            scope,
+            ptr: NonNull::dangling(),
+            head: 0,
+            len: 0,
+            cap,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty deque with room for `capacity` elements.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn with_capacity(scope: &'a Scope<'scope>, capacity: usize) -> Self {
+        let mut deque = Self::new(scope);
+        deque.reserve(capacity);
+        deque
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Makes sure there is room for `additional` more elements.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed > self.cap {
+            self.grow(cmp::max(needed, self.cap * 2));
+        }
+    }
+
+    /// Moves the elements to a buffer of `new_cap` elements, which
+    /// is the same buffer if it can grow in place.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn grow(&mut self, new_cap: usize) {
+        let old_cap = self.cap;
+        let (front, back) = self.as_mut_slices();
+        let (front_len, back_len) = (front.len(), back.len());
+
+        unsafe {
+            if self.scope.grow_last(self.ptr.as_ptr(), old_cap, new_cap) {
+                // The elements that wrapped around go after the old end.
+                let start = self.ptr.as_ptr();
+                ptr::copy_nonoverlapping(start, start.add(old_cap), back_len);
+            } else {
+                let new = self.scope.malloc::<T>(new_cap).expect("Allocation failed");
+                let front_start = self.ptr.as_ptr().add(self.head);
+
+                ptr::copy_nonoverlapping(front_start, new, front_len);
+                ptr::copy_nonoverlapping(self.ptr.as_ptr(), new.add(front_len), back_len);
+                self.ptr = NonNull::new_unchecked(new);
+                self.head = 0;
+            }
+        }
+
+        self.cap = new_cap;
+    }
+
+    /// The slot of the element at `index`, which may be `len`.
+    fn slot(&self, index: usize) -> usize {
+        if mem::size_of::<T>() == 0 {
+            return 0;
+        }
+
+        let slot = self.head + index;
+        if slot >= self.cap {
+            slot - self.cap
+        } else {
+            slot
+        }
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push_back(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+
+        unsafe { self.ptr.as_ptr().add(self.slot(self.len)).write(value) };
+        self.len += 1;
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push_front(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+
+        self.head = self.slot(self.cap - 1);
+        unsafe { self.ptr.as_ptr().add(self.head).write(value) };
+        self.len += 1;
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        Some(unsafe { self.ptr.as_ptr().add(self.slot(self.len)).read() })
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let value = unsafe { self.ptr.as_ptr().add(self.head).read() };
+        self.head = self.slot(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(unsafe { &*self.ptr.as_ptr().add(self.slot(index)) })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            Some(unsafe { &mut *self.ptr.as_ptr().add(self.slot(index)) })
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.wrapping_sub(1))
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(self.len.wrapping_sub(1))
+    }
+
+    /// Drops all elements, keeping the buffer.
+    pub fn clear(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        let (front, back) = (front as *mut [T], back as *mut [T]);
+
+        // Emptied first, in case a destructor panics.
+        self.len = 0;
+        self.head = 0;
+
+        unsafe {
+            ptr::drop_in_place(front);
+            ptr::drop_in_place(back);
+        }
+    }
+
+    /// The elements, split where the ring buffer wraps around.
+    /// The second slice is empty if it doesn't.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let front_len = cmp::min(self.len, self.cap - self.head);
+
+        unsafe {
+            let front = slice::from_raw_parts(self.ptr.as_ptr().add(self.head), front_len);
+            let back = slice::from_raw_parts(self.ptr.as_ptr(), self.len - front_len);
+            (front, back)
+        }
+    }
+
+    /// Like [`LocalVecDeque::as_slices`], but mutable.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let front_len = cmp::min(self.len, self.cap - self.head);
+
+        unsafe {
+            let front = slice::from_raw_parts_mut(self.ptr.as_ptr().add(self.head), front_len);
+            let back = slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len - front_len);
+            (front, back)
+        }
+    }
+
+    /// Iterates over the elements, front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.as_slices();
+
+        Iter {
+            front: front.iter(),
+            back: back.iter(),
+        }
+    }
+
+    /// Iterates over mutable references to the elements, front to back.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.as_mut_slices();
+
+        IterMut {
+            front: front.iter_mut(),
+            back: back.iter_mut(),
+        }
+    }
+}
+
+/// Drops the elements, and gives the buffer back
+/// if nothing was allocated after it.
+impl<T> Drop for LocalVecDeque<'_, '_, T> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { self.scope.shrink_last(self.ptr.as_ptr(), self.cap, 0) };
+    }
+}
+
+impl<T> Extend<T> for LocalVecDeque<'_, '_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> Index<usize> for LocalVecDeque<'_, '_, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for LocalVecDeque<'_, '_, T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LocalVecDeque<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<'d, T> IntoIterator for &'d LocalVecDeque<'_, '_, T> {
+    type Item = &'d T;
+    type IntoIter = Iter<'d, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'d, T> IntoIterator for &'d mut LocalVecDeque<'_, '_, T> {
+    type Item = &'d mut T;
+    type IntoIter = IterMut<'d, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of a [`LocalVecDeque`].
+pub struct Iter<'d, T> {
+    front: slice::Iter<'d, T>,
+    back: slice::Iter<'d, T>,
+}
+
+impl<'d, T> Iterator for Iter<'d, T> {
+    type Item = &'d T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator over mutable references to
+/// the elements of a [`LocalVecDeque`].
+pub struct IterMut<'d, T> {
+    front: slice::IterMut<'d, T>,
+    back: slice::IterMut<'d, T>,
+}
+
+impl<'d, T> Iterator for IterMut<'d, T> {
+    type Item = &'d mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.front.next() {
+            Some(value) => Some(value),
+            None => self.back.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.back.next_back() {
+            Some(value) => Some(value),
+            None => self.front.next_back(),
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalVecDeque;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::{collections::VecDeque, format, vec::Vec};
+    use core::cell::Cell;
+
+    #[test]
+    fn test_like_vecdeque() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut deque = LocalVecDeque::new(s);
+            let mut expected = VecDeque::new();
+
+            for n in 0..1000u32 {
+                match n % 7 {
+                    0 | 3 => {
+                        deque.push_front(n);
+                        expected.push_front(n);
+                    }
+                    1 | 4 | 5 => {
+                        deque.push_back(n);
+                        expected.push_back(n);
+                    }
+                    2 => assert_eq!(deque.pop_front(), expected.pop_front()),
+                    _ => assert_eq!(deque.pop_back(), expected.pop_back()),
+                }
+
+                assert_eq!(deque.len(), expected.len());
+                assert_eq!(deque.front(), expected.front());
+                assert_eq!(deque.back(), expected.back());
+            }
+
+            assert!(deque.iter().eq(&expected));
+            assert!(deque.iter().rev().eq(expected.iter().rev()));
+            assert_eq!(deque[10], expected[10]);
+        });
+    }
+
+    #[test]
+    fn test_grow_wrapped() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            // Grows in place, while wrapped around.
+            let mut deque = LocalVecDeque::with_capacity(s, 4);
+            deque.extend([2, 3]);
+            deque.push_front(1);
+            deque.push_front(0);
+            assert_eq!(deque.as_slices(), (&[0, 1][..], &[2, 3][..]));
+
+            deque.push_back(4);
+            assert!(deque.iter().copied().eq(0..5));
+            assert_eq!(deque.capacity(), 8);
+
+            // Moves to a new buffer, while wrapped around.
+            let mut other = LocalVecDeque::with_capacity(s, 2);
+            other.push_back(1);
+            other.push_front(0);
+            s.alloc(0u8);
+            other.push_back(2);
+            assert_eq!(other.as_slices(), (&[0, 1, 2][..], &[][..]));
+
+            for n in deque.iter_mut() {
+                *n *= 10;
+            }
+            assert_eq!(format!("{:?}", deque), "[0, 10, 20, 30, 40]");
+        });
+    }
+
+    #[test]
+    fn test_drops() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut deque = LocalVecDeque::new(s);
+            for _ in 0..10 {
+                deque.push_front(DropCount(&drops));
+                deque.push_back(DropCount(&drops));
+            }
+
+            drop(deque.pop_front());
+            assert_eq!(drops.get(), 1);
+
+            deque.clear();
+            assert_eq!(drops.get(), 20);
+
+            deque.push_back(DropCount(&drops));
+            drop(deque);
+            assert_eq!(drops.get(), 21);
+            assert_eq!(arena.allocated_bytes(), 0);
+        });
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut deque = LocalVecDeque::new(s);
+            for _ in 0..100 {
+                deque.push_front(());
+                deque.push_back(());
+            }
+
+            assert_eq!(deque.pop_front(), Some(()));
+            assert_eq!(deque.iter().count(), 199);
+            assert_eq!(deque.iter().collect::<Vec<_>>().len(), 199);
+        });
+    }
+}
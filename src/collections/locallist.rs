@@ -0,0 +1,583 @@
+use crate::arena::Scope;
+
+use core::{fmt, iter::FusedIterator, marker::PhantomData, mem::MaybeUninit, ptr::NonNull};
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    /// Uninitialized while the node is on the free list.
+    value: MaybeUninit<T>,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+/// A doubly linked list whose nodes live in the arena.
+///
+/// Every node is allocated on its own. Nodes of removed elements
+/// are kept on a free list and used again by later pushes, and all
+/// of them are given back when the region ends.
+/// ```
+/// use arenalloc::{arena::Arena, collections::locallist::LocalLinkedList};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut list = LocalLinkedList::new(s);
+///     list.push_back(2);
+///     list.push_back(3);
+///     list.push_front(1);
+///
+///     assert_eq!(list.pop_back(), Some(3));
+///     assert!(list.iter().eq(&[1, 2]));
+/// });
+/// ```
+pub struct LocalLinkedList<'a, 'scope, T> {
+    scope: &'a Scope<'scope>,
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+
+    /// Nodes without a value, linked through `next`.
+    free: Link<T>,
+
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'scope, T> LocalLinkedList<'a, 'scope, T> {
+    pub fn new(scope: &'a Scope<'scope>) -> Self {
+        Self {
+            scope,
+            head: None,
+            tail: None,
+            len: 0,
+            free: None,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|node| unsafe { Self::value(node) })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|node| unsafe { Self::value_mut(node) })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|node| unsafe { Self::value(node) })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|node| unsafe { Self::value_mut(node) })
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push_front(&mut self, value: T) {
+        unsafe { self.link(value, None, self.head) }
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push_back(&mut self, value: T) {
+        unsafe { self.link(value, self.tail, None) }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|node| unsafe { self.unlink(node) })
+    }
+
+    /// Drops every element. The nodes are kept, to be used again.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            head: self.head,
+            tail: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a cursor at the first element,
+    /// or at the "ghost" position if the list is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, 'a, 'scope, T> {
+        CursorMut {
+            current: self.head,
+            list: self,
+        }
+    }
+
+    /// Returns a cursor at the last element,
+    /// or at the "ghost" position if the list is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, 'a, 'scope, T> {
+        CursorMut {
+            current: self.tail,
+            list: self,
+        }
+    }
+
+    /// # Safety
+    /// `node` must be a node of a list, with a value.
+    unsafe fn value<'l>(node: NonNull<Node<T>>) -> &'l T {
+        (*node.as_ptr()).value.assume_init_ref()
+    }
+
+    /// # Safety
+    /// `node` must be a node of a list, with a value.
+    unsafe fn value_mut<'l>(node: NonNull<Node<T>>) -> &'l mut T {
+        (*node.as_ptr()).value.assume_init_mut()
+    }
+
+    /// Returns a node from the free list, or allocates one.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn node(&mut self, value: T) -> NonNull<Node<T>> {
+        let node = Node {
+            value: MaybeUninit::new(value),
+            prev: None,
+            next: None,
+        };
+
+        unsafe {
+            match self.free {
+                Some(free) => {
+                    self.free = (*free.as_ptr()).next;
+                    free.as_ptr().write(node);
+                    free
+                }
+                None => {
+                    let ptr = self.scope.malloc::<Node<T>>(1).expect("Allocation failed");
+                    ptr.write(node);
+                    NonNull::new_unchecked(ptr)
+                }
+            }
+        }
+    }
+
+    /// Inserts `value` between `prev` and `next`.
+    ///
+    /// # Safety
+    /// `prev` and `next` must be adjacent nodes of this list,
+    /// where `None` stands for before the head or after the tail.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    unsafe fn link(&mut self, value: T, prev: Link<T>, next: Link<T>) {
+        let node = self.node(value);
+        (*node.as_ptr()).prev = prev;
+        (*node.as_ptr()).next = next;
+
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = Some(node),
+            None => self.head = Some(node),
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = Some(node),
+            None => self.tail = Some(node),
+        }
+        self.len += 1;
+    }
+
+    /// Removes `node` from the list, and puts it on the free list.
+    ///
+    /// # Safety
+    /// `node` must be a node of this list.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        let Node { prev, next, .. } = *node.as_ptr();
+
+        match prev {
+            Some(prev) => (*prev.as_ptr()).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+
+        let value = (*node.as_ptr()).value.assume_init_read();
+        (*node.as_ptr()).next = self.free;
+        self.free = Some(node);
+        value
+    }
+}
+
+impl<T> Drop for LocalLinkedList<'_, '_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Extend<T> for LocalLinkedList<'_, '_, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LocalLinkedList<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'l, T> IntoIterator for &'l LocalLinkedList<'_, '_, T> {
+    type Item = &'l T;
+    type IntoIter = Iter<'l, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'l, T> IntoIterator for &'l mut LocalLinkedList<'_, '_, T> {
+    type Item = &'l mut T;
+    type IntoIter = IterMut<'l, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of a [`LocalLinkedList`].
+pub struct Iter<'l, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'l T>,
+}
+
+impl<'l, T> Iterator for Iter<'l, T> {
+    type Item = &'l T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = self.head?;
+        self.len -= 1;
+
+        unsafe {
+            self.head = (*node.as_ptr()).next;
+            Some(LocalLinkedList::value(node))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = self.tail?;
+        self.len -= 1;
+
+        unsafe {
+            self.tail = (*node.as_ptr()).prev;
+            Some(LocalLinkedList::value(node))
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator over mutable references to
+/// the elements of a [`LocalLinkedList`].
+pub struct IterMut<'l, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    len: usize,
+    marker: PhantomData<&'l mut T>,
+}
+
+impl<'l, T> Iterator for IterMut<'l, T> {
+    type Item = &'l mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = self.head?;
+        self.len -= 1;
+
+        unsafe {
+            self.head = (*node.as_ptr()).next;
+            Some(LocalLinkedList::value_mut(node))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = self.tail?;
+        self.len -= 1;
+
+        unsafe {
+            self.tail = (*node.as_ptr()).prev;
+            Some(LocalLinkedList::value_mut(node))
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// A position in a [`LocalLinkedList`], to walk it
+/// and insert and remove elements along the way.
+///
+/// Besides the elements, the cursor can be at a "ghost" position
+/// after the last element and before the first one.
+/// ```
+/// use arenalloc::{arena::Arena, collections::locallist::LocalLinkedList};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut list = LocalLinkedList::new(s);
+///     list.extend([1, 2, 4]);
+///
+///     let mut cursor = list.cursor_front_mut();
+///     while cursor.current().map_or(false, |n| *n < 4) {
+///         cursor.move_next();
+///     }
+///     cursor.insert_before(3);
+///
+///     assert!(list.iter().eq(&[1, 2, 3, 4]));
+/// });
+/// ```
+pub struct CursorMut<'l, 'a, 'scope, T> {
+    list: &'l mut LocalLinkedList<'a, 'scope, T>,
+
+    /// `None` at the ghost position.
+    current: Link<T>,
+}
+
+impl<T> CursorMut<'_, '_, '_, T> {
+    /// The element at the cursor, `None` at the ghost position.
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current
+            .map(|node| unsafe { LocalLinkedList::value_mut(node) })
+    }
+
+    /// Moves to the next element. From the
+    /// last element that is the ghost position.
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+    }
+
+    /// Moves to the previous element. From the
+    /// first element that is the ghost position.
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+    }
+
+    /// Inserts `value` before the cursor.
+    /// At the ghost position it becomes the last element.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn insert_before(&mut self, value: T) {
+        let prev = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).prev },
+            None => self.list.tail,
+        };
+
+        unsafe { self.list.link(value, prev, self.current) }
+    }
+
+    /// Inserts `value` after the cursor.
+    /// At the ghost position it becomes the first element.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn insert_after(&mut self, value: T) {
+        let next = match self.current {
+            Some(node) => unsafe { (*node.as_ptr()).next },
+            None => self.list.head,
+        };
+
+        unsafe { self.list.link(value, self.current, next) }
+    }
+
+    /// Removes the element at the cursor and returns it,
+    /// moving the cursor to the next element.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+
+        unsafe {
+            self.current = (*node.as_ptr()).next;
+            Some(self.list.unlink(node))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalLinkedList;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::{format, vec::Vec};
+    use core::cell::Cell;
+
+    #[test]
+    fn test_push_pop() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut list = LocalLinkedList::new(s);
+            assert_eq!(list.pop_front(), None);
+
+            for n in 0..10 {
+                list.push_back(n);
+                list.push_front(-n);
+            }
+
+            assert_eq!(list.len(), 20);
+            assert_eq!(list.front(), Some(&-9));
+            assert_eq!(list.back(), Some(&9));
+
+            *list.back_mut().unwrap() = 90;
+            assert_eq!(list.pop_back(), Some(90));
+            assert_eq!(list.pop_front(), Some(-9));
+            assert_eq!(
+                format!("{:?}", list.iter().take(3).collect::<Vec<_>>()),
+                "[-8, -7, -6]"
+            );
+        });
+    }
+
+    #[test]
+    fn test_nodes_are_reused() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut list = LocalLinkedList::new(s);
+            list.extend(0..100u64);
+            let allocated = arena.allocated_bytes();
+
+            list.clear();
+            assert!(list.is_empty());
+
+            list.extend(0..100);
+            assert_eq!(arena.allocated_bytes(), allocated);
+            assert!(list.iter().copied().eq(0..100));
+        });
+    }
+
+    #[test]
+    fn test_iter() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut list = LocalLinkedList::new(s);
+            list.extend(1..=5);
+
+            for n in &mut list {
+                *n *= 10;
+            }
+
+            assert!(list.iter().rev().eq(&[50, 40, 30, 20, 10]));
+
+            let mut iter = list.iter();
+            assert_eq!(iter.len(), 5);
+            assert_eq!(iter.next(), Some(&10));
+            assert_eq!(iter.next_back(), Some(&50));
+            assert_eq!(iter.len(), 3);
+            assert!(iter.eq(&[20, 30, 40]));
+        });
+    }
+
+    #[test]
+    fn test_cursor() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut list = LocalLinkedList::new(s);
+
+            let mut cursor = list.cursor_front_mut();
+            assert_eq!(cursor.current(), None);
+            cursor.insert_after(2);
+            cursor.insert_before(4);
+            cursor.move_next();
+            cursor.insert_before(1);
+            cursor.insert_after(3);
+            assert!(list.iter().eq(&[1, 2, 3, 4]));
+
+            let mut cursor = list.cursor_back_mut();
+            cursor.move_prev();
+            assert_eq!(cursor.remove_current(), Some(3));
+            assert_eq!(cursor.current(), Some(&mut 4));
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.remove_current(), Some(1));
+            assert_eq!(cursor.current(), Some(&mut 2));
+
+            assert!(list.iter().eq(&[2, 4]));
+            assert_eq!(list.len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_drops() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut list = LocalLinkedList::new(s);
+
+            for _ in 0..10 {
+                list.push_back(DropCount(&drops));
+            }
+
+            drop(list.pop_front());
+            assert_eq!(drops.get(), 1);
+
+            let mut cursor = list.cursor_front_mut();
+            drop(cursor.remove_current());
+            assert_eq!(drops.get(), 2);
+
+            drop(list);
+            assert_eq!(drops.get(), 10);
+        });
+    }
+}
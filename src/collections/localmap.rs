@@ -0,0 +1,76 @@
+//! Hash maps and sets from [`hashbrown`] that allocate
+//! their tables in the arena, with the `hashbrown` feature.
+//!
+//! They are the hashbrown types with a [`Scope`] as allocator,
+//! so every method of `hashbrown::HashMap` is available.
+//! Create them with `new_in` or `with_capacity_in`. A table that
+//! grows is moved, and the old one is only given back if it was
+//! the last allocation.
+//! ```
+//! use arenalloc::{arena::Arena, collections::localmap::LocalHashMap};
+//!
+//! let arena = Arena::new();
+//!
+//! arena.region(|s| {
+//!     let mut lengths = LocalHashMap::new_in(*s);
+//!
+//!     for word in "the quick brown fox".split(' ') {
+//!         lengths.insert(word, word.len());
+//!     }
+//!
+//!     assert_eq!(lengths["quick"], 5);
+//!     assert!(arena.allocated_bytes() > 0);
+//! });
+//! ```
+
+use crate::arena::Scope;
+
+pub use hashbrown::DefaultHashBuilder;
+
+/// A `hashbrown::HashMap` whose table lives in the arena.
+pub type LocalHashMap<'scope, K, V, S = DefaultHashBuilder> =
+    hashbrown::HashMap<K, V, S, Scope<'scope>>;
+
+/// A `hashbrown::HashSet` whose table lives in the arena.
+pub type LocalHashSet<'scope, T, S = DefaultHashBuilder> = hashbrown::HashSet<T, S, Scope<'scope>>;
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalHashMap, LocalHashSet};
+    use crate::arena::Arena;
+
+    #[test]
+    fn test_map() {
+        let arena = Arena::with_capacity(64);
+
+        arena.region(|s| {
+            let mut squares = LocalHashMap::with_capacity_in(4, *s);
+
+            for n in 0..1000u64 {
+                squares.insert(n, n * n);
+            }
+
+            assert_eq!(squares.len(), 1000);
+            assert_eq!(squares[&30], 900);
+            assert_eq!(squares.remove(&30), Some(900));
+            assert!(!squares.contains_key(&30));
+            assert!(arena.bucket_count() > 1);
+        });
+
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+
+    #[test]
+    fn test_set() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut seen = LocalHashSet::new_in(*s);
+
+            let words = "a rose is a rose is a rose";
+            let unique = words.split(' ').filter(|w| seen.insert(*w)).count();
+
+            assert_eq!(unique, 3);
+        });
+    }
+}
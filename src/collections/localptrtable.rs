@@ -0,0 +1,4 @@
+//! [`LocalPtrTable`] is created by `Scope::alloc_ptr_table`,
+//! so it is defined next to `Scope` in `arenalloc-core`.
+
+pub use arenalloc_core::arena::ptrtable::*;
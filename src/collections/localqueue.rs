@@ -0,0 +1,292 @@
+use crate::arena::Scope;
+
+use core::{cell::Cell, marker::PhantomData, mem, ptr};
+
+/// A buffer of `T`'s living in the arena.
+/// Only the first `len` elements are initialized.
+struct RawBuf<T> {
+    ptr: *mut T,
+    len: usize,
+    cap: usize,
+}
+
+impl<T> Clone for RawBuf<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RawBuf<T> {}
+
+impl<T> RawBuf<T> {
+    fn empty() -> Self {
+        let cap = if mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            0
+        };
+
+        Self {
+            ptr: ptr::NonNull::dangling().as_ptr(),
+            len: 0,
+            cap,
+        }
+    }
+
+    /// Drops the initialized elements in `start..len`.
+    unsafe fn drop_from(self, start: usize) {
+        let remaining = ptr::slice_from_raw_parts_mut(self.ptr.add(start), self.len - start);
+        ptr::drop_in_place(remaining);
+    }
+}
+
+/// A queue for alternating produce / consume phases.
+///
+/// Pushed values go into a write buffer, and [`LocalBatchQueue::drain_batch`]
+/// swaps it out for a second buffer, so values can be pushed while
+/// the previous batch is being consumed. Both buffers live in the arena
+/// and are reused once they are large enough.
+/// ```
+/// use arenalloc::{arena::Arena, collections::localqueue::LocalBatchQueue};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let queue = LocalBatchQueue::new(s);
+///     queue.push(1);
+///
+///     for n in queue.drain_batch() {
+///         queue.push(n + 1);
+///     }
+///
+///     assert_eq!(queue.drain_batch().collect::<Vec<_>>(), [2]);
+/// });
+/// ```
+pub struct LocalBatchQueue<'a, 'scope, T> {
+    scope: &'a Scope<'scope>,
+
+    /// The buffer `push` writes into.
+    write: Cell<RawBuf<T>>,
+
+    /// An empty buffer to swap in on the next drain,
+    /// returned by the last dropped `Batch`.
+    spare: Cell<Option<RawBuf<T>>>,
+
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'scope, T> LocalBatchQueue<'a, 'scope, T> {
+    pub fn new(scope: &'a Scope<'scope>) -> Self {
+        Self {
+            scope,
+            write: Cell::new(RawBuf::empty()),
+            spare: Cell::new(None),
+            marker: PhantomData,
+        }
+    }
+
+    /// The number of values pushed since the last drain.
+    pub fn len(&self) -> usize {
+        self.write.get().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push(&self, value: T) {
+        let mut buf = self.write.get();
+
+        if buf.len == buf.cap {
+            buf = self.grow(buf);
+        }
+
+        unsafe {
+            buf.ptr.add(buf.len).write(value);
+        }
+        buf.len += 1;
+        self.write.set(buf);
+    }
+
+    /// Returns a buffer with room for at least one more element.
+    /// An empty spare buffer is reused if there is one,
+    /// otherwise the elements are moved into a new, larger buffer.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn grow(&self, buf: RawBuf<T>) -> RawBuf<T> {
+        if buf.len == 0 {
+            if let Some(spare) = self.spare.get().filter(|spare| spare.cap > buf.cap) {
+                self.spare.set(None);
+                return spare;
+            }
+        }
+
+        let cap = if buf.cap == 0 { 4 } else { buf.cap * 2 };
+        let ptr = self.scope.malloc::<T>(cap).expect("Allocation failed");
+
+        unsafe {
+            ptr::copy_nonoverlapping(buf.ptr, ptr, buf.len);
+        }
+
+        RawBuf {
+            ptr,
+            len: buf.len,
+            cap,
+        }
+    }
+
+    /// Returns an iterator over the values pushed since the
+    /// last drain, in FIFO order. Values that are not consumed are
+    /// dropped together with the iterator.
+    pub fn drain_batch(&self) -> Batch<'_, T> {
+        let buf = self.write.get();
+
+        let next = self.spare.take().unwrap_or_else(RawBuf::empty);
+        self.write.set(next);
+
+        Batch {
+            spare: &self.spare,
+            buf,
+            pos: 0,
+        }
+    }
+}
+
+impl<T> Drop for LocalBatchQueue<'_, '_, T> {
+    fn drop(&mut self) {
+        unsafe { self.write.get().drop_from(0) }
+    }
+}
+
+/// The iterator returned by [`LocalBatchQueue::drain_batch`].
+pub struct Batch<'q, T> {
+    spare: &'q Cell<Option<RawBuf<T>>>,
+    buf: RawBuf<T>,
+    pos: usize,
+}
+
+impl<T> Iterator for Batch<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.pos == self.buf.len {
+            return None;
+        }
+
+        let value = unsafe { self.buf.ptr.add(self.pos).read() };
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.buf.len - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Batch<'_, T> {}
+
+impl<T> Drop for Batch<'_, T> {
+    fn drop(&mut self) {
+        let buf = self.buf;
+
+        // Hand the buffer back before dropping the elements,
+        // so a panicking destructor doesn't lose it.
+        let keep = match self.spare.get() {
+            Some(spare) => spare.cap < buf.cap,
+            None => true,
+        };
+        if keep {
+            self.spare.set(Some(RawBuf { len: 0, ..buf }));
+        }
+
+        let start = self.pos;
+        self.pos = buf.len;
+        unsafe { buf.drop_from(start) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalBatchQueue;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+
+    #[test]
+    fn test_batches_reuse_buffers() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let queue = LocalBatchQueue::new(s);
+            let mut used = 0;
+
+            for cycle in 0..5 {
+                for n in 0..100 {
+                    queue.push(n);
+                }
+
+                let batch = queue.drain_batch().collect::<Vec<_>>();
+                assert_eq!(batch, (0..100).collect::<Vec<_>>());
+
+                if cycle == 0 {
+                    used = arena.allocated_bytes();
+                }
+                assert_eq!(arena.allocated_bytes(), used);
+            }
+        });
+    }
+
+    #[test]
+    fn test_push_while_draining() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let queue = LocalBatchQueue::new(s);
+            queue.push(1);
+
+            let mut used = 0;
+            for cycle in 0..5 {
+                let batch = queue.drain_batch();
+                assert_eq!(batch.len(), 1);
+
+                for n in batch {
+                    queue.push(n * 2);
+                }
+
+                if cycle == 0 {
+                    used = arena.allocated_bytes();
+                }
+                assert_eq!(arena.allocated_bytes(), used);
+            }
+
+            assert_eq!(queue.drain_batch().collect::<Vec<_>>(), [32]);
+        });
+    }
+
+    #[test]
+    fn test_drops() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let queue = LocalBatchQueue::new(s);
+
+            for _ in 0..10 {
+                queue.push(DropCount(&drops));
+            }
+
+            let mut batch = queue.drain_batch();
+            drop(batch.next());
+            assert_eq!(drops.get(), 1);
+
+            queue.push(DropCount(&drops));
+            drop(batch);
+            assert_eq!(drops.get(), 10);
+
+            drop(queue);
+            assert_eq!(drops.get(), 11);
+        });
+    }
+}
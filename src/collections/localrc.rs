@@ -0,0 +1,362 @@
+use crate::arena::Scope;
+
+use core::{
+    borrow::Borrow,
+    cell::Cell,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::Deref,
+    ptr::{self, NonNull},
+};
+
+/// The counts and the value of a [`LocalRc`], in the arena.
+struct RcBox<T> {
+    strong: Cell<usize>,
+    weak: Cell<usize>,
+    value: ManuallyDrop<T>,
+}
+
+impl<T> RcBox<T> {
+    // The counts are borrowed on their own, because
+    // they are also used while the value is being dropped.
+
+    /// # Safety
+    /// `ptr` must point to an `RcBox` in the arena.
+    unsafe fn strong<'r>(ptr: NonNull<Self>) -> &'r Cell<usize> {
+        &*ptr::addr_of!((*ptr.as_ptr()).strong)
+    }
+
+    /// # Safety
+    /// `ptr` must point to an `RcBox` in the arena.
+    unsafe fn weak<'r>(ptr: NonNull<Self>) -> &'r Cell<usize> {
+        &*ptr::addr_of!((*ptr.as_ptr()).weak)
+    }
+}
+
+/// A reference counted pointer to a value in the arena.
+/// The value is dropped when the last `LocalRc` is dropped,
+/// the memory is given back together with the rest of the arena.
+/// ```
+/// use arenalloc::{arena::Arena, collections::localrc::LocalRc};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let shared = LocalRc::new(s, String::from("subtree"));
+///     let other = LocalRc::clone(&shared);
+///
+///     assert!(LocalRc::ptr_eq(&shared, &other));
+///     assert_eq!(LocalRc::strong_count(&shared), 2);
+/// });
+/// ```
+pub struct LocalRc<'a, 'scope, T> {
+    scope: PhantomData<&'a Scope<'scope>>,
+    ptr: NonNull<RcBox<T>>,
+    marker: PhantomData<T>,
+}
+
+/// A non-owning pointer to the value of a [`LocalRc`],
+/// created by [`LocalRc::downgrade`].
+pub struct LocalWeak<'a, 'scope, T> {
+    scope: PhantomData<&'a Scope<'scope>>,
+    ptr: NonNull<RcBox<T>>,
+}
+
+impl<'a, 'scope, T> LocalRc<'a, 'scope, T> {
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn new(scope: &'a Scope<'scope>, value: T) -> Self {
+        let ptr = scope.malloc::<RcBox<T>>(1).expect("Allocation failed");
+
+        unsafe {
+            ptr.write(RcBox {
+                strong: Cell::new(1),
+                weak: Cell::new(0),
+                value: ManuallyDrop::new(value),
+            });
+
+            Self::from_inner(NonNull::new_unchecked(ptr))
+        }
+    }
+
+    /// # Safety
+    /// The value behind `ptr` must be alive, and the
+    /// strong count must include the returned `LocalRc`.
+    unsafe fn from_inner(ptr: NonNull<RcBox<T>>) -> Self {
+        Self {
+            scope: PhantomData,
+            ptr,
+            marker: PhantomData,
+        }
+    }
+
+    fn strong(&self) -> &Cell<usize> {
+        unsafe { RcBox::strong(self.ptr) }
+    }
+
+    fn weak(&self) -> &Cell<usize> {
+        unsafe { RcBox::weak(self.ptr) }
+    }
+
+    // These are associated functions, like the ones of `Rc`,
+    // so they don't shadow methods of `T`.
+
+    pub fn downgrade(this: &Self) -> LocalWeak<'a, 'scope, T> {
+        this.weak().set(this.weak().get() + 1);
+
+        LocalWeak {
+            scope: PhantomData,
+            ptr: this.ptr,
+        }
+    }
+
+    pub fn strong_count(this: &Self) -> usize {
+        this.strong().get()
+    }
+
+    pub fn weak_count(this: &Self) -> usize {
+        this.weak().get()
+    }
+
+    /// Returns true if both point to the same allocation.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    /// Returns a mutable reference to the value,
+    /// if there are no other `LocalRc`s or `LocalWeak`s to it.
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::strong_count(this) == 1 && Self::weak_count(this) == 0 {
+            Some(unsafe { &mut (*this.ptr.as_ptr()).value })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Clone for LocalRc<'_, '_, T> {
+    fn clone(&self) -> Self {
+        self.strong().set(self.strong().get() + 1);
+
+        unsafe { Self::from_inner(self.ptr) }
+    }
+}
+
+impl<T> Drop for LocalRc<'_, '_, T> {
+    fn drop(&mut self) {
+        self.strong().set(self.strong().get() - 1);
+
+        if self.strong().get() == 0 {
+            unsafe { ManuallyDrop::drop(&mut (*self.ptr.as_ptr()).value) }
+        }
+    }
+}
+
+impl<T> Deref for LocalRc<'_, '_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &(*self.ptr.as_ptr()).value }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LocalRc<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for LocalRc<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for LocalRc<'_, '_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Eq> Eq for LocalRc<'_, '_, T> {}
+
+impl<T: PartialOrd> PartialOrd for LocalRc<'_, '_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: Ord> Ord for LocalRc<'_, '_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: Hash> Hash for LocalRc<'_, '_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T> AsRef<T> for LocalRc<'_, '_, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> Borrow<T> for LocalRc<'_, '_, T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, 'scope, T> LocalWeak<'a, 'scope, T> {
+    /// Returns a `LocalRc` to the value, if it wasn't dropped yet.
+    pub fn upgrade(&self) -> Option<LocalRc<'a, 'scope, T>> {
+        // The `RcBox` itself lives as long as the arena.
+        let strong = unsafe { RcBox::strong(self.ptr) };
+
+        if strong.get() == 0 {
+            return None;
+        }
+
+        strong.set(strong.get() + 1);
+        Some(unsafe { LocalRc::from_inner(self.ptr) })
+    }
+
+    pub fn strong_count(&self) -> usize {
+        unsafe { RcBox::strong(self.ptr).get() }
+    }
+
+    /// Returns true if both point to the same allocation.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        ptr::eq(self.ptr.as_ptr(), other.ptr.as_ptr())
+    }
+}
+
+impl<T> Clone for LocalWeak<'_, '_, T> {
+    fn clone(&self) -> Self {
+        let weak = unsafe { RcBox::weak(self.ptr) };
+        weak.set(weak.get() + 1);
+
+        Self {
+            scope: PhantomData,
+            ptr: self.ptr,
+        }
+    }
+}
+
+impl<T> Drop for LocalWeak<'_, '_, T> {
+    fn drop(&mut self) {
+        let weak = unsafe { RcBox::weak(self.ptr) };
+        weak.set(weak.get() - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalRc, LocalWeak};
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use alloc::vec::Vec;
+    use core::cell::{Cell, RefCell};
+
+    #[test]
+    fn test_clone_drop() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let a = LocalRc::new(s, DropCount(&drops));
+            let b = a.clone();
+            let weak = LocalRc::downgrade(&a);
+
+            assert_eq!(LocalRc::strong_count(&a), 2);
+            assert_eq!(LocalRc::weak_count(&a), 1);
+
+            drop(a);
+            assert_eq!(drops.get(), 0);
+            assert!(weak.upgrade().is_some());
+
+            drop(b);
+            assert_eq!(drops.get(), 1);
+            assert!(weak.upgrade().is_none());
+            assert_eq!(weak.strong_count(), 0);
+        });
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut a = LocalRc::new(s, 1);
+            *LocalRc::get_mut(&mut a).unwrap() += 1;
+
+            let weak = LocalRc::downgrade(&a);
+            assert!(LocalRc::get_mut(&mut a).is_none());
+            drop(weak);
+
+            let b = a.clone();
+            assert!(LocalRc::get_mut(&mut a).is_none());
+            drop(b);
+
+            assert_eq!(LocalRc::get_mut(&mut a), Some(&mut 2));
+        });
+    }
+
+    struct Node<'a, 'scope> {
+        parent: RefCell<Option<LocalWeak<'a, 'scope, Node<'a, 'scope>>>>,
+        children: RefCell<Vec<LocalRc<'a, 'scope, Node<'a, 'scope>>>>,
+        value: u32,
+    }
+
+    #[test]
+    fn test_tree() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let node = |value| {
+                LocalRc::new(
+                    s,
+                    Node {
+                        parent: RefCell::new(None),
+                        children: RefCell::new(Vec::new()),
+                        value,
+                    },
+                )
+            };
+
+            let root = node(0);
+            let shared = node(1);
+
+            for _ in 0..2 {
+                *shared.parent.borrow_mut() = Some(LocalRc::downgrade(&root));
+                root.children.borrow_mut().push(shared.clone());
+            }
+
+            assert_eq!(LocalRc::strong_count(&shared), 3);
+
+            let parent = shared.parent.borrow().as_ref().unwrap().upgrade().unwrap();
+            assert!(LocalRc::ptr_eq(&parent, &root));
+            assert_eq!(parent.children.borrow()[1].value, 1);
+        });
+    }
+
+    #[test]
+    fn test_traits() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let a = LocalRc::new(s, 1);
+            let b = LocalRc::new(s, 2);
+
+            assert_ne!(a, b);
+            assert!(a < b);
+            assert_eq!(a, LocalRc::clone(&a));
+            assert_eq!(alloc::format!("{:?} {}", a, b), "1 2");
+        });
+    }
+}
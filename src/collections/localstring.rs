@@ -0,0 +1,269 @@
+use super::localvec::LocalVec;
+use crate::arena::Scope;
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str,
+};
+
+/// A growable string whose bytes live in the arena.
+/// See also [`format_in!`](crate::format_in).
+/// ```
+/// use arenalloc::{arena::Arena, collections::localstring::LocalString};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut greeting = LocalString::new(s);
+///     greeting.push_str("hello");
+///     greeting.push('!');
+///
+///     assert_eq!(&*greeting, "hello!");
+/// });
+/// ```
+pub struct LocalString<'a, 'scope> {
+    /// Always valid UTF-8.
+    bytes: LocalVec<'a, 'scope, u8>,
+}
+
+impl<'a, 'scope> LocalString<'a, 'scope> {
+    pub fn new(scope: &'a Scope<'scope>) -> Self {
+        Self {
+            bytes: LocalVec::new(scope),
+        }
+    }
+
+    /// Creates an empty string with room for `capacity` bytes.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn with_capacity(scope: &'a Scope<'scope>, capacity: usize) -> Self {
+        Self {
+            bytes: LocalVec::with_capacity(scope, capacity),
+        }
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend(s.bytes());
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.chars().next_back()?;
+        self.bytes.truncate(self.len() - c.len_utf8());
+        Some(c)
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl Deref for LocalString<'_, '_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl fmt::Write for LocalString<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl Extend<char> for LocalString<'_, '_> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.bytes.reserve(iter.size_hint().0);
+
+        for c in iter {
+            self.push(c);
+        }
+    }
+}
+
+impl<'s> Extend<&'s str> for LocalString<'_, '_> {
+    fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(s);
+        }
+    }
+}
+
+impl fmt::Display for LocalString<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for LocalString<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Clone for LocalString<'_, '_> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+        }
+    }
+}
+
+impl PartialEq for LocalString<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialEq<str> for LocalString<'_, '_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for LocalString<'_, '_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Eq for LocalString<'_, '_> {}
+
+impl PartialOrd for LocalString<'_, '_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LocalString<'_, '_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for LocalString<'_, '_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl AsRef<str> for LocalString<'_, '_> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<[u8]> for LocalString<'_, '_> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for LocalString<'_, '_> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+/// Formats into a new [`LocalString`](crate::collections::localstring::LocalString)
+/// in the arena of a Scope. Unlike [`arena_format!`](crate::arena_format),
+/// the result can still grow.
+/// ```
+/// use arenalloc::{arena::Arena, format_in};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut line = format_in!(s, "{} + {}", 1, 2);
+///     line.push_str(" = 3");
+///
+///     assert_eq!(&*line, "1 + 2 = 3");
+/// });
+/// ```
+#[macro_export]
+macro_rules! format_in {
+    ($scope:expr, $($arg:tt)*) => {{
+        let mut s = $crate::collections::localstring::LocalString::new($scope);
+        ::core::fmt::Write::write_fmt(&mut s, ::core::format_args!($($arg)*))
+            .expect("a formatting trait implementation returned an error");
+        s
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalString;
+    use crate::arena::Arena;
+
+    use alloc::collections::BTreeSet;
+
+    #[test]
+    fn test_push() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut string = LocalString::new(s);
+
+            for _ in 0..100 {
+                string.push_str("ab");
+                string.push('é');
+            }
+
+            assert_eq!(string.len(), 400);
+            assert_eq!(string.pop(), Some('é'));
+            assert_eq!(string.pop(), Some('b'));
+            assert!(string.ends_with("éa"));
+        });
+    }
+
+    #[test]
+    fn test_format_in() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let string = format_in!(s, "{:>5}|{:?}", 42, "x");
+
+            assert_eq!(&*string, "   42|\"x\"");
+            assert_eq!(alloc::format!("{:?}", string), "\"   42|\\\"x\\\"\"");
+        });
+    }
+
+    #[test]
+    fn test_traits() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let a = format_in!(s, "{}", 12);
+            let mut b = a.clone();
+            b.push('3');
+
+            assert_eq!(a, "12");
+            assert_eq!(b, *"123");
+            assert!(a < b);
+
+            let mut set = BTreeSet::new();
+            set.insert(a);
+            set.insert(b);
+            assert!(set.contains("123"));
+        });
+    }
+}
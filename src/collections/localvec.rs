@@ -1 +1,441 @@
+use super::localbox::LocalBox;
 use crate::arena::Scope;
+
+use core::{
+    borrow::{Borrow, BorrowMut},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A growable vector whose elements live in the arena.
+///
+/// When it runs out of room the buffer grows in place if it is the
+/// last allocation of the arena. Otherwise the elements are moved to
+/// a new buffer twice as large, and the old buffer is not reused.
+/// ```
+/// use arenalloc::{arena::Arena, collections::localvec::LocalVec};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut v = LocalVec::new(s);
+///     v.push(1);
+///     v.extend([2, 3]);
+///
+///     assert_eq!(v[1], 2);
+///     assert_eq!(v.pop(), Some(3));
+///     assert_eq!(*v, [1, 2]);
+/// });
+/// ```
+pub struct LocalVec<'a, 'scope, T> {
+    scope: &'a Scope<'scope>,
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'scope, T> LocalVec<'a, 'scope, T> {
+    pub fn new(scope: &'a Scope<'scope>) -> Self {
+        let cap = if mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            0
+        };
+
+        Self {
+            scope,
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty vector with room for `capacity` elements.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn with_capacity(scope: &'a Scope<'scope>, capacity: usize) -> Self {
+        let mut v = Self::new(scope);
+        v.reserve(capacity);
+        v
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Makes sure there is room for `additional` more elements.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+
+        if needed > self.cap {
+            self.grow_to(needed.max(self.cap.saturating_mul(2)).max(4));
+        }
+    }
+
+    /// Grows the buffer to `cap` elements, in place if possible.
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn grow_to(&mut self, cap: usize) {
+        if self.cap > 0 && unsafe { self.scope.grow_last(self.ptr.as_ptr(), self.cap, cap) } {
+            self.cap = cap;
+            return;
+        }
+
+        let ptr = self.scope.malloc::<T>(cap).expect("Allocation failed");
+
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr, self.len);
+            self.ptr = NonNull::new_unchecked(ptr);
+        }
+        self.cap = cap;
+    }
+
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    pub fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+
+        unsafe {
+            self.ptr.as_ptr().add(self.len).write(value);
+        }
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        Some(unsafe { self.ptr.as_ptr().add(self.len).read() })
+    }
+
+    /// Drops the elements after the first `len`.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let tail =
+            ptr::slice_from_raw_parts_mut(unsafe { self.ptr.as_ptr().add(len) }, self.len - len);
+
+        // Shorten first, so a panicking destructor can't cause a double drop.
+        self.len = len;
+        unsafe { ptr::drop_in_place(tail) }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+
+    /// Converts into a boxed slice, which owns the elements.
+    /// The unused capacity is not given back.
+    pub fn into_boxed_slice(self) -> LocalBox<'a, 'scope, [T]> {
+        // Gives back the spare capacity, so the box can give back the rest.
+        unsafe {
+            self.scope
+                .shrink_last(self.ptr.as_ptr(), self.cap, self.len)
+        };
+
+        let (scope, slice) = (
+            self.scope,
+            ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len),
+        );
+        mem::forget(self);
+
+        unsafe { LocalBox::from_ptr(scope, slice) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+/// Drops the elements, and gives the buffer back
+/// if nothing was allocated after it.
+impl<T> Drop for LocalVec<'_, '_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.as_mut_slice());
+            self.scope.shrink_last(self.ptr.as_ptr(), self.cap, 0);
+        }
+    }
+}
+
+impl<T> Deref for LocalVec<'_, '_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for LocalVec<'_, '_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> Extend<T> for LocalVec<'_, '_, T> {
+    #[cfg_attr(feature = "debug-track", track_caller)]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Clone> Clone for LocalVec<'_, '_, T> {
+    fn clone(&self) -> Self {
+        let mut v = Self::with_capacity(self.scope, self.len);
+        v.extend(self.iter().cloned());
+        v
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LocalVec<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for LocalVec<'_, '_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for LocalVec<'_, '_, T> {}
+
+impl<T: PartialOrd> PartialOrd for LocalVec<'_, '_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord> Ord for LocalVec<'_, '_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T: Hash> Hash for LocalVec<'_, '_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<T> AsRef<[T]> for LocalVec<'_, '_, T> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T> AsMut<[T]> for LocalVec<'_, '_, T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T> Borrow<[T]> for LocalVec<'_, '_, T> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T> BorrowMut<[T]> for LocalVec<'_, '_, T> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalVec;
+    use crate::{arena::Arena, testutil::DropCount};
+
+    use core::cell::Cell;
+
+    #[test]
+    fn test_push_pop() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+
+            for n in 0..1000u64 {
+                v.push(n);
+            }
+
+            assert_eq!(v.len(), 1000);
+            assert!(v.capacity() >= 1000);
+            assert!(v.iter().copied().eq(0..1000));
+
+            v[10] = 0;
+            assert_eq!(v[10], 0);
+
+            for n in (0..1000).rev() {
+                assert_eq!(v.pop(), Some(if n == 10 { 0 } else { n }));
+            }
+            assert_eq!(v.pop(), None);
+            assert!(v.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_extend() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::with_capacity(s, 2);
+            assert_eq!(v.capacity(), 4);
+
+            v.extend("hello".chars());
+            v.extend(Some('!'));
+
+            assert_eq!(*v, ['h', 'e', 'l', 'l', 'o', '!']);
+        });
+    }
+
+    #[test]
+    fn test_grows_in_place() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+            v.extend(0..4u32);
+
+            let ptr = v.as_ptr();
+            v.extend(4..100);
+
+            assert_eq!(v.as_ptr(), ptr);
+            assert_eq!(arena.allocated_bytes(), v.capacity() * 4);
+
+            s.alloc(0u8);
+            v.extend(100..1000);
+            assert_ne!(v.as_ptr(), ptr);
+            assert!(v.iter().copied().eq(0..1000));
+        });
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+
+            for _ in 0..100 {
+                v.push(());
+            }
+
+            assert_eq!(v.len(), 100);
+            assert_eq!(arena.allocated_bytes(), 0);
+        });
+    }
+
+    #[test]
+    fn test_drops() {
+        let arena = Arena::new();
+        let drops = Cell::new(0);
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+
+            for _ in 0..10 {
+                v.push(DropCount(&drops));
+            }
+
+            drop(v.pop());
+            assert_eq!(drops.get(), 1);
+
+            v.truncate(5);
+            assert_eq!(drops.get(), 5);
+
+            drop(v);
+            assert_eq!(drops.get(), 10);
+        });
+    }
+
+    #[test]
+    fn test_traits() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut a = LocalVec::new(s);
+            a.extend([3, 1, 2]);
+
+            let mut b = a.clone();
+            assert_eq!(a, b);
+            assert_eq!(alloc::format!("{:?}", b), "[3, 1, 2]");
+
+            b.as_mut().sort();
+            assert!(b < a);
+            assert_eq!(b.as_ref(), [1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn test_gives_back_last() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+            v.extend(0..100u32);
+            drop(v);
+            assert_eq!(arena.allocated_bytes(), 0);
+
+            let mut v = LocalVec::with_capacity(s, 100);
+            v.extend(0..10u32);
+            let boxed = v.into_boxed_slice();
+            assert_eq!(arena.allocated_bytes(), 40);
+
+            drop(boxed);
+            assert_eq!(arena.allocated_bytes(), 0);
+        });
+    }
+
+    #[cfg(feature = "debug-track")]
+    #[test]
+    fn test_tracks_call_site() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut v = LocalVec::new(s);
+            s.alloc(0u8);
+
+            // Moves to a new allocation every time it grows.
+            for n in 0..100u32 {
+                v.push(n);
+            }
+        });
+
+        let report = arena.allocation_report();
+        assert_eq!(report[0].location.file(), file!());
+        assert_eq!(report[0].location.line(), line!() - 6);
+        assert_eq!(report[0].largest.size(), 128 * 4);
+    }
+}
@@ -1,2 +1,11 @@
+pub mod collect;
 pub mod localbox;
+pub mod localdeque;
+pub mod locallist;
+#[cfg(feature = "hashbrown")]
+pub mod localmap;
+pub mod localptrtable;
+pub mod localqueue;
+pub mod localrc;
+pub mod localstring;
 pub mod localvec;
@@ -0,0 +1,280 @@
+//! Deserializing with [`serde`] straight into the arena,
+//! with the `serde` feature.
+//!
+//! An [`ArenaSeed`] is a `DeserializeSeed` that copies strings
+//! and sequences into the arena, instead of into a `String` or `Vec`
+//! on the heap. Use it on its own, or from the visitor of a type
+//! that holds arena references. It works with any format, like
+//! `serde_json::Deserializer` instead of the `SeqDeserializer` here.
+//! ```
+//! use arenalloc::{arena::Arena, de::ArenaSeed};
+//! use serde::de::{value::{Error, SeqDeserializer}, DeserializeSeed};
+//!
+//! let arena = Arena::new();
+//!
+//! arena.region(|s| {
+//!     let input = SeqDeserializer::<_, Error>::new([1u32, 2, 3].iter().copied());
+//!     let numbers = ArenaSeed::<[u32]>::new(*s).deserialize(input).unwrap();
+//!
+//!     assert_eq!(numbers, [1, 2, 3]);
+//! });
+//! ```
+
+use crate::{
+    arena::Scope,
+    collections::{localbox::LocalBox, localvec::LocalVec},
+};
+
+use core::{fmt, marker::PhantomData, str};
+use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, SeqAccess, Visitor};
+
+/// Deserializes a `&'scope T` into the arena, where `T` is
+/// `str` or a slice. See the [module docs](self).
+///
+/// The elements of a slice are never dropped, like
+/// everything allocated with [`Scope::alloc`].
+pub struct ArenaSeed<'scope, T: ?Sized> {
+    scope: Scope<'scope>,
+    marker: PhantomData<fn() -> &'scope T>,
+}
+
+impl<'scope, T: ?Sized> ArenaSeed<'scope, T> {
+    pub fn new(scope: Scope<'scope>) -> Self {
+        Self {
+            scope,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for ArenaSeed<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for ArenaSeed<'_, T> {}
+
+impl<T: ?Sized> fmt::Debug for ArenaSeed<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaSeed").finish_non_exhaustive()
+    }
+}
+
+/// Copies the string into the arena.
+/// ```
+/// use arenalloc::{arena::Arena, de::ArenaSeed};
+/// use serde::de::{value::{Error, StrDeserializer}, DeserializeSeed};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let input = StrDeserializer::<Error>::new("hello");
+///     let text = ArenaSeed::<str>::new(*s).deserialize(input).unwrap();
+///
+///     assert_eq!(text, "hello");
+/// });
+/// ```
+impl<'de, 'scope> DeserializeSeed<'de> for ArenaSeed<'scope, str> {
+    type Value = &'scope str;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de, 'scope> Visitor<'de> for ArenaSeed<'scope, str> {
+    type Value = &'scope str;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(self.scope.alloc_str(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        match str::from_utf8(v) {
+            Ok(s) => self.visit_str(s),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Bytes(v), &self)),
+        }
+    }
+}
+
+/// Collects the sequence into the arena. Byte strings are
+/// accepted as well, each byte deserialized as an element.
+impl<'de, 'scope, T> DeserializeSeed<'de> for ArenaSeed<'scope, [T]>
+where
+    T: de::Deserialize<'de>,
+{
+    type Value = &'scope [T];
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'scope, T> Visitor<'de> for ArenaSeed<'scope, [T]>
+where
+    T: de::Deserialize<'de>,
+{
+    type Value = &'scope [T];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut v = LocalVec::with_capacity(&self.scope, seq.size_hint().unwrap_or(0));
+
+        while let Some(element) = seq.next_element()? {
+            v.push(element);
+        }
+
+        Ok(leak(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        let mut v = LocalVec::with_capacity(&self.scope, bytes.len());
+
+        for &byte in bytes {
+            v.push(T::deserialize(byte.into_deserializer())?);
+        }
+
+        Ok(leak(v))
+    }
+}
+
+/// Turns `v` into a slice that lives as long as the Scope.
+/// The elements are not dropped.
+fn leak<'scope, T>(v: LocalVec<'_, 'scope, T>) -> &'scope [T] {
+    LocalBox::leak(v.into_boxed_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaSeed;
+    use crate::arena::{Arena, Scope};
+
+    use core::fmt;
+    use serde::de::{
+        self,
+        value::{BytesDeserializer, Error, MapDeserializer, SeqDeserializer, StrDeserializer},
+        DeserializeSeed, Deserializer, MapAccess, Visitor,
+    };
+
+    /// A document that borrows all of its strings from the arena.
+    #[derive(Debug, PartialEq)]
+    struct Person<'scope> {
+        name: &'scope str,
+        city: &'scope str,
+    }
+
+    struct PersonSeed<'scope>(Scope<'scope>);
+
+    impl<'de, 'scope> DeserializeSeed<'de> for PersonSeed<'scope> {
+        type Value = Person<'scope>;
+
+        fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+            d.deserialize_map(self)
+        }
+    }
+
+    impl<'de, 'scope> Visitor<'de> for PersonSeed<'scope> {
+        type Value = Person<'scope>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a person")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let seed = ArenaSeed::<str>::new(self.0);
+            let (mut name, mut city) = (None, None);
+
+            while let Some(key) = map.next_key_seed(seed)? {
+                match key {
+                    "name" => name = Some(map.next_value_seed(seed)?),
+                    "city" => city = Some(map.next_value_seed(seed)?),
+                    _ => return Err(de::Error::unknown_field(key, &["name", "city"])),
+                }
+            }
+
+            Ok(Person {
+                name: name.ok_or_else(|| de::Error::missing_field("name"))?,
+                city: city.ok_or_else(|| de::Error::missing_field("city"))?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_nested() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let fields = [("name", "Ferris"), ("city", "Utrecht")];
+            let input = MapDeserializer::<_, Error>::new(fields.iter().copied());
+            let person = PersonSeed(*s).deserialize(input).unwrap();
+
+            assert_eq!(
+                person,
+                Person {
+                    name: "Ferris",
+                    city: "Utrecht"
+                }
+            );
+            assert_eq!(arena.allocated_bytes(), "nameFerriscityUtrecht".len());
+        });
+    }
+
+    #[test]
+    fn test_bytes() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let bytes = ArenaSeed::<[u8]>::new(*s)
+                .deserialize(BytesDeserializer::<Error>::new(b"abc"))
+                .unwrap();
+            assert_eq!(bytes, b"abc");
+
+            let wide = ArenaSeed::<[u32]>::new(*s)
+                .deserialize(BytesDeserializer::<Error>::new(b"abc"))
+                .unwrap();
+            assert_eq!(wide, [97, 98, 99]);
+
+            let text = ArenaSeed::<str>::new(*s)
+                .deserialize(BytesDeserializer::<Error>::new(b"abc"))
+                .unwrap();
+            assert_eq!(text, "abc");
+
+            assert!(ArenaSeed::<str>::new(*s)
+                .deserialize(BytesDeserializer::<Error>::new(&[0xFF]))
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_invalid() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let input = SeqDeserializer::<_, Error>::new([1i64, -1].iter().copied());
+            assert!(ArenaSeed::<[u32]>::new(*s).deserialize(input).is_err());
+
+            let input = SeqDeserializer::<_, Error>::new(["a"].iter().copied());
+            assert!(ArenaSeed::<str>::new(*s).deserialize(input).is_err());
+
+            let input = StrDeserializer::<Error>::new("abc");
+            assert!(ArenaSeed::<[u8]>::new(*s).deserialize(input).is_err());
+        });
+    }
+}
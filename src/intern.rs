@@ -0,0 +1,209 @@
+//! Deduplicating strings in the arena.
+
+use crate::arena::{ArenaIndex, Scope};
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::marker::PhantomData;
+
+/// A handle to a string in an [`Interner`].
+///
+/// Symbols of the same interner are equal
+/// if and only if their strings are equal.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Symbol<I = u32>(I);
+
+impl<I: ArenaIndex> Symbol<I> {
+    /// The position of the string in the interner,
+    /// counting in the order the strings were interned.
+    pub fn index(self) -> usize {
+        self.0.index()
+    }
+}
+
+/// Stores every distinct string once in the arena,
+/// and hands out [`Symbol`]s for them.
+/// ```
+/// use arenalloc::{arena::Arena, intern::Interner};
+///
+/// let arena = Arena::new();
+///
+/// arena.region(|s| {
+///     let mut interner = Interner::<u32>::new(s);
+///
+///     let a = interner.get_or_intern("foo");
+///     let b = interner.get_or_intern("bar");
+///
+///     assert_eq!(interner.get_or_intern("foo"), a);
+///     assert_ne!(a, b);
+///     assert_eq!(interner.resolve(b), "bar");
+///     assert_eq!(interner.get("baz"), None);
+/// });
+/// ```
+pub struct Interner<'scope, I = u32> {
+    scope: Scope<'scope>,
+    symbols: BTreeMap<&'scope str, Symbol<I>>,
+    strings: Vec<&'scope str>,
+    marker: PhantomData<I>,
+}
+
+impl<'scope, I: ArenaIndex> Interner<'scope, I> {
+    pub fn new(scope: &Scope<'scope>) -> Self {
+        Self {
+            scope: *scope,
+            symbols: BTreeMap::new(),
+            strings: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// The number of distinct strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the symbol of `s`, copying `s` into the
+    /// arena if it wasn't interned before.
+    ///
+    /// # Panics
+    /// Panics if the number of strings overflows `I`.
+    pub fn get_or_intern(&mut self, s: &str) -> Symbol<I> {
+        match self.get(s) {
+            Some(symbol) => symbol,
+            None => self.insert(s, I::from_usize(self.strings.len())),
+        }
+    }
+
+    /// Like [`Interner::get_or_intern`], but returns `None` if
+    /// `s` is new and the number of strings would overflow `I`.
+    pub fn try_get_or_intern(&mut self, s: &str) -> Option<Symbol<I>> {
+        match self.get(s) {
+            Some(symbol) => Some(symbol),
+            None => Some(self.insert(s, I::try_from_usize(self.strings.len())?)),
+        }
+    }
+
+    /// Copies `s` into the arena as the string of `index`.
+    fn insert(&mut self, s: &str, index: I) -> Symbol<I> {
+        let symbol = Symbol(index);
+        let s = self.scope.alloc_str(s);
+
+        self.symbols.insert(s, symbol);
+        self.strings.push(s);
+        symbol
+    }
+
+    /// Returns the symbol of `s`, if it was interned.
+    pub fn get(&self, s: &str) -> Option<Symbol<I>> {
+        self.symbols.get(s).copied()
+    }
+
+    /// Returns the string of `symbol`.
+    ///
+    /// # Panics
+    /// Panics if `symbol` is not from this interner.
+    pub fn resolve(&self, symbol: Symbol<I>) -> &'scope str {
+        self.strings[symbol.index()]
+    }
+
+    /// Iterates over the symbols and their strings,
+    /// in the order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol<I>, &'scope str)> + '_ {
+        self.strings
+            .iter()
+            .enumerate()
+            .map(|(index, s)| (Symbol(I::from_usize(index)), *s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interner, Symbol};
+    use crate::arena::Arena;
+
+    use alloc::{string::ToString, vec::Vec};
+    use core::mem;
+
+    /// An edge of a graph whose nodes are interned names.
+    #[allow(dead_code)]
+    struct Edge<I> {
+        from: Symbol<I>,
+        to: Symbol<I>,
+    }
+
+    #[test]
+    fn test_deduplicates() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut interner = Interner::<u32>::new(s);
+
+            let symbols = ["a", "b", "a", "c", "b", ""]
+                .iter()
+                .map(|word| interner.get_or_intern(word))
+                .collect::<Vec<_>>();
+
+            assert_eq!(symbols[0], symbols[2]);
+            assert_eq!(symbols[1], symbols[4]);
+            assert_eq!(interner.len(), 4);
+
+            let used = arena.allocated_bytes();
+            interner.get_or_intern("c");
+            assert_eq!(arena.allocated_bytes(), used);
+
+            let strings = interner.iter().map(|(_, s)| s).collect::<Vec<_>>();
+            assert_eq!(strings, ["a", "b", "c", ""]);
+            assert_eq!(interner.resolve(symbols[3]), "c");
+        });
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "interns 65536 strings")]
+    #[should_panic(expected = "overflows the index type u16")]
+    fn test_overflow() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut interner = Interner::<u16>::new(s);
+
+            for n in 0..=65536u32 {
+                interner.get_or_intern(&n.to_string());
+            }
+        });
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "interns 65536 strings")]
+    fn test_boundary() {
+        let arena = Arena::new();
+
+        arena.region(|s| {
+            let mut interner = Interner::<u16>::new(s);
+
+            for n in 0..65536u32 {
+                let symbol = interner.try_get_or_intern(&n.to_string()).unwrap();
+                assert_eq!(symbol.index(), n as usize);
+            }
+            assert_eq!(interner.len(), 65536);
+
+            let used = arena.allocated_bytes();
+            assert_eq!(interner.try_get_or_intern("65536"), None);
+            assert_eq!(interner.len(), 65536);
+            assert_eq!(arena.allocated_bytes(), used);
+
+            let last = interner.try_get_or_intern("65535").unwrap();
+            assert_eq!(last.index(), 65535);
+            assert_eq!(interner.resolve(last), "65535");
+        });
+    }
+
+    #[test]
+    fn test_edge_size() {
+        assert_eq!(mem::size_of::<Edge<u16>>(), 4);
+        assert_eq!(mem::size_of::<Edge<u32>>(), 8);
+        assert_eq!(mem::size_of::<Symbol>(), mem::size_of::<u32>());
+    }
+}
@@ -1,7 +1,47 @@
+//! Experimental arena allocator.
+//!
+//! The allocator itself lives in [`arenalloc_core`],
+//! which is re-exported as [`arena`]. This crate adds
+//! the arena-backed collections and a string
+//! [`intern`]er on top of it.
+//!
+//! # Minimum supported Rust version
+//!
+//! The MSRV is Rust 1.65, with default features
+//! as well as with `--no-default-features`.
+//!
+//! | feature          | minimum Rust |
+//! |------------------|--------------|
+//! | `collections`    | 1.65         |
+//! | `debug-poison`   | 1.65         |
+//! | `type-stats`     | 1.65         |
+//! | `debug-track`    | 1.65         |
+//! | `bench-support`  | 1.65         |
+//! | `allocator-api2` | 1.65         |
+//! | `std`            | 1.65         |
+//! | `tracing`        | 1.65         |
+//! | `mmap`           | 1.65         |
+//! | `sanitizers`     | 1.65         |
+//! | `bytemuck`       | 1.65         |
+//! | `hashbrown`      | 1.65         |
+//! | `serde`          | 1.65         |
+//! | `nightly`        | nightly      |
+//!
+//! The `nightly` feature enables unstable compiler features,
+//! like implicitly converting a `LocalBox` to a trait object,
+//! and using an `Arena` or `Scope` as `Allocator`.
 #![no_std]
-#![feature(alloc_layout_extra)]
+#![cfg_attr(feature = "nightly", feature(coerce_unsized, unsize))]
 
 extern crate alloc;
 
-pub mod arena;
+pub use arenalloc_core::{arena, arena_format};
+#[cfg(feature = "collections")]
 pub mod collections;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "collections")]
+pub mod intern;
+#[cfg(all(test, feature = "collections"))]
+#[path = "../arenalloc-core/src/testutil.rs"]
+mod testutil;